@@ -0,0 +1,86 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use cw_graph::semcores::{lint_core, SemanticCore, Severity};
+
+const USAGE: &str = "usage:
+  semcore lint <file.json>...           check semantic core files, named after their file stem
+  semcore lint --bundled                check the cores bundled with the contract
+  semcore export <core> [<namespace>]   print the type definitions instantiate loads for a core";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("lint") => lint(&args[1..]),
+        Some("export") => export(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn lint(args: &[String]) -> Result<ExitCode, String> {
+    let files: Vec<(String, String, String)> = match args {
+        [] => return Err(USAGE.to_string()),
+        [flag] if flag == "--bundled" => SemanticCore::ALL
+            .iter()
+            .map(|core| (format!("{}.json", core.name()), core.name().to_string(), core.json().to_string()))
+            .collect(),
+        paths => paths
+            .iter()
+            .map(|path| {
+                let json = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+                let name = Path::new(path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                Ok((path.clone(), name, json))
+            })
+            .collect::<Result<_, String>>()?,
+    };
+
+    let (mut errors, mut warnings) = (0, 0);
+    for (path, name, json) in &files {
+        for diagnostic in lint_core(name, json) {
+            match diagnostic.severity {
+                Severity::Error => errors += 1,
+                Severity::Warning => warnings += 1,
+            }
+            eprintln!("{}: {}", path, diagnostic);
+        }
+    }
+    eprintln!("{} file(s) checked: {} error(s), {} warning(s)", files.len(), errors, warnings);
+
+    Ok(if errors > 0 { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+fn export(args: &[String]) -> Result<ExitCode, String> {
+    let (core, namespace) = match args {
+        [core] => (core, None),
+        [core, namespace] => (core, Some(namespace)),
+        _ => return Err(USAGE.to_string()),
+    };
+    let core = SemanticCore::from_str(core).ok_or_else(|| format!("unknown semantic core: {}", core))?;
+
+    // Same validation as lint, a broken bundled core would otherwise panic while parsing
+    let diagnostics = lint_core(core.name(), core.json());
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        for diagnostic in diagnostics {
+            eprintln!("{}.json: {}", core.name(), diagnostic);
+        }
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let types = match namespace {
+        Some(namespace) => core.get_namespaced_types(namespace),
+        None => core.get_types(),
+    };
+    let json = serde_json::to_string_pretty(&types).map_err(|err| err.to_string())?;
+    println!("{}", json);
+    Ok(ExitCode::SUCCESS)
+}
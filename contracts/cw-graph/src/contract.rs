@@ -1,14 +1,17 @@
+// ContractError::TypeConflict carries the full type constraints and is matched on by clients
+#![allow(clippy::result_large_err)]
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{to_json_binary, Addr, Api, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult};
 use cw2::set_contract_version;
 
 use crate::error::ContractError;
-use crate::execute::{execute_create_cyberlink, execute_create_cyberlinks, execute_create_named_cyberlink, execute_delete_cyberlink, execute_update_admins, execute_update_cyberlink, execute_update_executors, execute_create_cyberlink2};
+use crate::execute::{record_change, register_type, execute_create_cyberlink, execute_create_owned_named_cyberlink, execute_create_cyberlinks, execute_import_subgraph, execute_create_named_cyberlink, execute_delete_cyberlink, execute_update_admins, execute_update_cyberlink, execute_update_executors, execute_create_cyberlink2, execute_update_type_rules, execute_set_type_lifecycle, execute_update_handle_fee, execute_claim_handle, execute_release_handle, execute_transfer_handle, execute_transfer_ownership, execute_transfer_ownerships, execute_accept_ownership, execute_cancel_ownership_transfer, execute_set_acl, execute_restore_cyberlink, execute_purge_cyberlink, execute_start_ranking, execute_continue_ranking};
 use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::query::{query_config, query_cyberlink_by_fid, query_cyberlinks_by_gids, query_cyberlinks_set_by_gids, query_cyberlinks_by_owner, query_cyberlinks_by_owner_time, query_cyberlinks_by_owner_time_any, query_cyberlink_by_gid, query_last_gid, query_cyberlinks_by_fids, query_state, query_cyberlinks_set_by_fids, query_cyberlinks_by_type, query_cyberlinks_by_from, query_cyberlinks_by_to, query_cyberlinks_by_owner_and_type, query_graph_stats};
+use crate::query::{query_config, query_cyberlink_by_fid, query_cyberlinks_by_gids, query_cyberlinks_set_by_gids, query_cyberlinks_by_owner, query_cyberlinks_by_owner_time, query_cyberlinks_by_owner_time_any, query_cyberlink_by_gid, query_last_gid, query_cyberlinks_by_fids, query_cyberlinks_by_fid_prefix, query_state, query_cyberlinks_set_by_fids, query_cyberlinks_by_type, query_cyberlinks_by_from, query_cyberlinks_by_to, query_cyberlinks_by_owner_and_type, query_cyberlinks_by_field, query_graph_stats, query_node_stats, query_reactions, query_feed, query_suggest_connections, query_mutual_connections, query_rank, query_top_ranked, query_export_subgraph, query_match, query_types, query_type_info, query_resolve_handle, query_pending_transfer, query_acl, query_deleted_since, query_changes, query_cyberlinks_by_type_time};
 use crate::semcores::SemanticCore;
-use crate::state::{cyberlinks, Config, CyberlinkState, CONFIG, GID, NAMED_CYBERLINKS};
+use crate::state::{cyberlinks, ChangeOp, Config, CyberlinkState, TypeKind, TypeLifecycle, TypeMeta, CONFIG, GID, NAMED_CYBERLINKS};
 
 const CONTRACT_NAME: &str = "crates.io:cw-graph";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -25,6 +28,7 @@ pub fn instantiate(
     let config = Config {
         admins: map_validate(deps.api, &msg.admins)?,
         executors: map_validate(deps.api, &msg.executers)?,
+        handle_fee: None,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -36,7 +40,12 @@ pub fn instantiate(
     // Load selected semantic cores
     for core_name in msg.semantic_cores {
         if let Some(core) = SemanticCore::from_str(&core_name) {
-            load_semantic_core(deps.branch(), &env, &info, core)?;
+            load_semantic_core(deps.branch(), &env, &info, core, false)?;
+        }
+    }
+    for core_name in msg.namespaced_semantic_cores {
+        if let Some(core) = SemanticCore::from_str(&core_name) {
+            load_semantic_core(deps.branch(), &env, &info, core, true)?;
         }
     }
 
@@ -55,9 +64,12 @@ fn create_base_types(deps: DepsMut, env: &Env, info: &MessageInfo) -> Result<(),
         owner: info.sender.clone(),
         created_at: env.block.time,
         updated_at: None,
-        fid: Some("".to_string()),
+        deleted_at: None,
+        fid: Some("Type".to_string()),
     })?;
     NAMED_CYBERLINKS.save(deps.storage, "Type", &id)?;
+    record_change(deps.storage, id, ChangeOp::Create, env.block.time)?;
+    register_type(deps.storage, "Type", TypeMeta::unregistered())?;
 
     // Create Any type
     let id = GID.load(deps.storage)? + 1;
@@ -70,20 +82,40 @@ fn create_base_types(deps: DepsMut, env: &Env, info: &MessageInfo) -> Result<(),
         owner: info.sender.clone(),
         created_at: env.block.time,
         updated_at: None,
-        fid: Some("".to_string()),
+        deleted_at: None,
+        fid: Some("Any".to_string()),
     })?;
     NAMED_CYBERLINKS.save(deps.storage, "Any", &id)?;
+    record_change(deps.storage, id, ChangeOp::Create, env.block.time)?;
+    register_type(deps.storage, "Any", TypeMeta::unregistered())?;
     Ok(())
 }
 
-fn load_semantic_core(deps: DepsMut, env: &Env, info: &MessageInfo, core: SemanticCore) -> Result<(), ContractError> {
-    let types = core.get_types();
+fn load_semantic_core(deps: DepsMut, env: &Env, info: &MessageInfo, core: SemanticCore, namespaced: bool) -> Result<(), ContractError> {
+    let types = if namespaced {
+        core.get_namespaced_types(core.name())
+    } else {
+        core.get_types()
+    };
     
     for type_def in types {
         // Skip entries without an ID (already filtered in get_types, but just to be safe)
         if let Some(id_value) = &type_def.id {
+            // Cores must not silently overwrite names defined by the base types or other cores
+            if NAMED_CYBERLINKS.has(deps.storage, id_value) {
+                return Err(ContractError::NameAlreadyExists { name: id_value.clone() });
+            }
+
             let id = GID.load(deps.storage)? + 1;
             GID.save(deps.storage, &id)?;
+
+            let meta = TypeMeta {
+                semcore: Some(core.name().to_string()),
+                kind: type_def.kind.clone().unwrap_or_else(|| TypeKind::infer(&type_def.from, &type_def.to)),
+                allow_self_loop: type_def.allow_self_loop.unwrap_or(false),
+                lifecycle: TypeLifecycle::Active,
+                indexed_fields: type_def.indexed_fields.clone().unwrap_or_default(),
+            };
             
             let cyberlink_state = CyberlinkState {
                 type_: type_def.type_,
@@ -93,11 +125,14 @@ fn load_semantic_core(deps: DepsMut, env: &Env, info: &MessageInfo, core: Semant
                 owner: info.sender.clone(),
                 created_at: env.block.time,
                 updated_at: None,
-                fid: Some("".to_string()),
+                deleted_at: None,
+                fid: Some(id_value.clone()),
             };
 
             cyberlinks().save(deps.storage, id, &cyberlink_state)?;
             NAMED_CYBERLINKS.save(deps.storage, id_value, &id)?;
+            record_change(deps.storage, id, ChangeOp::Create, env.block.time)?;
+            register_type(deps.storage, id_value, meta)?;
         }
     }
     
@@ -117,6 +152,12 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::CreateNamedCyberlink { name, cyberlink } => execute_create_named_cyberlink(deps, env, info, name, cyberlink),
+        ExecuteMsg::CreateOwnedNamedCyberlink { name, cyberlink } => execute_create_owned_named_cyberlink(deps, env, info, name, cyberlink),
+        ExecuteMsg::TransferOwnership { fid, new_owner, require_accept } => execute_transfer_ownership(deps, env, info, fid, new_owner, require_accept),
+        ExecuteMsg::TransferOwnerships { fids, new_owner, require_accept } => execute_transfer_ownerships(deps, env, info, fids, new_owner, require_accept),
+        ExecuteMsg::AcceptOwnership { fid } => execute_accept_ownership(deps, env, info, fid),
+        ExecuteMsg::CancelOwnershipTransfer { fid } => execute_cancel_ownership_transfer(deps, env, info, fid),
+        ExecuteMsg::SetAcl { fid, editors, deleters } => execute_set_acl(deps, env, info, fid, editors, deleters),
         ExecuteMsg::CreateCyberlink { cyberlink } => execute_create_cyberlink(deps, env, info, cyberlink),
         ExecuteMsg::CreateCyberlink2 {
             node_type,
@@ -137,10 +178,21 @@ pub fn execute(
             link_to_existing_id
         ),
         ExecuteMsg::CreateCyberlinks { cyberlinks } => execute_create_cyberlinks(deps, env, info, cyberlinks),
+        ExecuteMsg::ImportSubgraph { import, entries } => execute_import_subgraph(deps, env, info, import, entries),
         ExecuteMsg::UpdateCyberlink { fid, value } => execute_update_cyberlink(deps, env, info, fid, value),
         ExecuteMsg::DeleteCyberlink { fid } => execute_delete_cyberlink(deps, env, info, fid),
+        ExecuteMsg::RestoreCyberlink { fid } => execute_restore_cyberlink(deps, env, info, fid),
+        ExecuteMsg::PurgeCyberlink { fid } => execute_purge_cyberlink(deps, env, info, fid),
         ExecuteMsg::UpdateAdmins { new_admins } => execute_update_admins(deps, env, info, new_admins),
         ExecuteMsg::UpdateExecutors { new_executors } => execute_update_executors(deps, env, info, new_executors),
+        ExecuteMsg::UpdateTypeRules { type_, kind, allow_self_loop, indexed_fields } => execute_update_type_rules(deps, env, info, type_, kind, allow_self_loop, indexed_fields),
+        ExecuteMsg::SetTypeLifecycle { type_, lifecycle } => execute_set_type_lifecycle(deps, env, info, type_, lifecycle),
+        ExecuteMsg::UpdateHandleFee { fee } => execute_update_handle_fee(deps, env, info, fee),
+        ExecuteMsg::ClaimHandle { handle, account } => execute_claim_handle(deps, env, info, handle, account),
+        ExecuteMsg::ReleaseHandle { handle } => execute_release_handle(deps, env, info, handle),
+        ExecuteMsg::TransferHandle { handle, new_owner } => execute_transfer_handle(deps, env, info, handle, new_owner),
+        ExecuteMsg::StartRanking { link_types, damping, iterations } => execute_start_ranking(deps, env, info, link_types, damping, iterations),
+        ExecuteMsg::ContinueRanking { limit } => execute_continue_ranking(deps, env, info, limit),
     }
 }
 
@@ -150,26 +202,51 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
         QueryMsg::DebugState {} => to_json_binary(&query_state(deps)?),
         QueryMsg::GetGraphStats { owner, type_ } => to_json_binary(&query_graph_stats(deps, owner, type_)?),
+        QueryMsg::NodeStats { fid } => to_json_binary(&query_node_stats(deps, fid)?),
+        QueryMsg::Reactions { target_fid } => to_json_binary(&query_reactions(deps, target_fid)?),
+        QueryMsg::Feed { account_fid, start_after, limit } => to_json_binary(&query_feed(deps, account_fid, start_after, limit)?),
+        QueryMsg::SuggestConnections { fid, link_type, limit } => to_json_binary(&query_suggest_connections(deps, fid, link_type, limit)?),
+        QueryMsg::MutualConnections { a, b, link_type } => to_json_binary(&query_mutual_connections(deps, a, b, link_type)?),
+
+        QueryMsg::Match { patterns, start_after, limit } => to_json_binary(&query_match(deps, patterns, start_after, limit)?),
+
+        QueryMsg::ExportSubgraph { root_fids, depth, link_types, cursor, limit } => to_json_binary(&query_export_subgraph(deps, root_fids, depth, link_types, cursor, limit)?),
+        QueryMsg::Rank { fid } => to_json_binary(&query_rank(deps, fid)?),
+        QueryMsg::TopRanked { type_, start_after, order, limit } => to_json_binary(&query_top_ranked(deps, type_, start_after, order, limit)?),
+
+        QueryMsg::Types { start_after, order, limit } => to_json_binary(&query_types(deps, start_after, order, limit)?),
+        QueryMsg::TypeInfo { type_ } => to_json_binary(&query_type_info(deps, type_)?),
+
+        QueryMsg::ResolveHandle { handle } => to_json_binary(&query_resolve_handle(deps, handle)?),
         
+        QueryMsg::Changes { after_seq, start_after, order, limit } => to_json_binary(&query_changes(deps, after_seq, start_after, order, limit)?),
+
         QueryMsg::LastGID {} => to_json_binary(&query_last_gid(deps)?),
         QueryMsg::CyberlinkByGID { gid } => to_json_binary(&query_cyberlink_by_gid(deps, gid)?),
-        QueryMsg::CyberlinksByGIDs { start_after_gid, limit} => to_json_binary(&query_cyberlinks_by_gids(deps, start_after_gid, limit)?),
+        QueryMsg::CyberlinksByGIDs { start_after, order, limit, include_deleted } => to_json_binary(&query_cyberlinks_by_gids(deps, start_after, order, limit, include_deleted)?),
+        QueryMsg::DeletedSince { time, start_after, order, limit } => to_json_binary(&query_deleted_since(deps, time, start_after, order, limit)?),
         QueryMsg::CyberlinksSetByGIDs { gids } => to_json_binary(&query_cyberlinks_set_by_gids(deps, gids)?),
         
         QueryMsg::CyberlinkByFID { fid } => to_json_binary(&query_cyberlink_by_fid(deps, fid)?),
-        QueryMsg::CyberlinksByFIDs { start_after_fid, limit } => to_json_binary(&query_cyberlinks_by_fids(deps, start_after_fid, limit)?),
+        QueryMsg::CyberlinksByFIDs { start_after, order, limit, include_deleted } => to_json_binary(&query_cyberlinks_by_fids(deps, start_after, order, limit, include_deleted)?),
+        QueryMsg::CyberlinksByFIDPrefix { prefix, start_after, order, limit, include_deleted } => to_json_binary(&query_cyberlinks_by_fid_prefix(deps, prefix, start_after, order, limit, include_deleted)?),
         QueryMsg::CyberlinksSetByFIDs { fids } => to_json_binary(&query_cyberlinks_set_by_fids(deps, fids)?),
+        QueryMsg::Acl { fid } => to_json_binary(&query_acl(deps, fid)?),
+        QueryMsg::PendingTransfer { fid } => to_json_binary(&query_pending_transfer(deps, fid)?),
         
-        QueryMsg::CyberlinksByOwner { owner, start_after_gid, limit } => to_json_binary(&query_cyberlinks_by_owner(deps, owner, start_after_gid, limit)?),
-        QueryMsg::CyberlinksByOwnerTime { owner, start_time, end_time, start_after_gid, limit } =>
-            to_json_binary(&query_cyberlinks_by_owner_time(deps, env, owner, start_time, end_time, start_after_gid, limit)?),
-        QueryMsg::CyberlinksByOwnerTimeAny { owner, start_time, end_time, start_after_gid, limit } =>
-            to_json_binary(&query_cyberlinks_by_owner_time_any(deps, env, owner, start_time, end_time, start_after_gid, limit)?),
-
-        QueryMsg::CyberlinksByType { type_, start_after_gid, limit } => to_json_binary(&query_cyberlinks_by_type(deps, type_, start_after_gid, limit)?),
-        QueryMsg::CyberlinksByFrom { from, start_after_gid, limit } => to_json_binary(&query_cyberlinks_by_from(deps, from, start_after_gid, limit)?),
-        QueryMsg::CyberlinksByTo { to, start_after_gid, limit } => to_json_binary(&query_cyberlinks_by_to(deps, to, start_after_gid, limit)?),
-        QueryMsg::CyberlinksByOwnerAndType { owner, type_, start_after_gid, limit } => to_json_binary(&query_cyberlinks_by_owner_and_type(deps, owner, type_, start_after_gid, limit)?),
+        QueryMsg::CyberlinksByOwner { owner, start_after, order, limit, include_deleted } => to_json_binary(&query_cyberlinks_by_owner(deps, owner, start_after, order, limit, include_deleted)?),
+        QueryMsg::CyberlinksByOwnerTime { owner, start_time, end_time, start_after, order, limit, include_deleted } =>
+            to_json_binary(&query_cyberlinks_by_owner_time(deps, env, owner, start_time, end_time, start_after, order, limit, include_deleted)?),
+        QueryMsg::CyberlinksByOwnerTimeAny { owner, start_time, end_time, start_after, order, limit, include_deleted } =>
+            to_json_binary(&query_cyberlinks_by_owner_time_any(deps, env, owner, start_time, end_time, start_after, order, limit, include_deleted)?),
+
+        QueryMsg::CyberlinksByType { type_, start_after, order, limit, include_deleted, filter } => to_json_binary(&query_cyberlinks_by_type(deps, type_, start_after, order, limit, include_deleted, filter)?),
+        QueryMsg::CyberlinksByTypeTime { type_, start_time, end_time, time_field, order, start_after, limit, include_deleted } =>
+            to_json_binary(&query_cyberlinks_by_type_time(deps, env, type_, start_time, end_time, time_field, order, start_after, limit, include_deleted)?),
+        QueryMsg::CyberlinksByFrom { from, start_after, order, limit, include_deleted, filter } => to_json_binary(&query_cyberlinks_by_from(deps, from, start_after, order, limit, include_deleted, filter)?),
+        QueryMsg::CyberlinksByTo { to, start_after, order, limit, include_deleted, filter } => to_json_binary(&query_cyberlinks_by_to(deps, to, start_after, order, limit, include_deleted, filter)?),
+        QueryMsg::CyberlinksByOwnerAndType { owner, type_, start_after, order, limit, include_deleted } => to_json_binary(&query_cyberlinks_by_owner_and_type(deps, owner, type_, start_after, order, limit, include_deleted)?),
+        QueryMsg::CyberlinksByField { type_, field, value, start_after, order, limit } => to_json_binary(&query_cyberlinks_by_field(deps, type_, field, value, start_after, order, limit)?),
     }
 }
 
@@ -1,4 +1,5 @@
 use cosmwasm_std::{StdError};
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,13 +7,40 @@ pub enum ContractError {
     #[error("Deleted cyberlink: {fid}")]
     DeletedCyberlink { fid: String },
 
+    #[error("Cyberlink is not deleted: {fid}")]
+    NotDeleted { fid: String },
+
     #[error("Not found: {fid}")]
     NotFound { fid: String },
 
+    #[error("Already reacted to: {target}")]
+    AlreadyReacted { target: String },
+
+    #[error("No ranking run in progress")]
+    NoRankingRun {},
+
+    #[error("Invalid ranking: {reason}")]
+    InvalidRanking { reason: String },
+
     // TODO: revisit and change to id: String
     #[error("Particular links is not allowed - from: {from}, to: {to}, type: {type_}")]
     InvalidCyberlink {from: String, to: String, type_: String},
 
+    #[error("Self-loop is not allowed for type '{type_}' on: {fid}")]
+    SelfLoopNotAllowed { type_: String, fid: String },
+
+    #[error("Import entry {index} has no id")]
+    MissingImportId { index: usize },
+
+    #[error("Cyberlink {fid} is still referenced by {by}")]
+    StillReferenced { fid: String, by: String },
+
+    #[error("Type is retired: {type_}")]
+    TypeRetired { type_: String },
+
+    #[error("Indexed fields can only change while type '{type_}' has no cyberlinks")]
+    IndexedFieldsInUse { type_: String },
+
     #[error("Type not exists: {type_}")]
     TypeNotExists { type_: String },
 
@@ -41,12 +69,39 @@ pub enum ContractError {
     #[error("Cannot change cyberlink {field}: ID {id} from {original} to {new}")]
     CannotChangeLinks { id: String, field: String, original: String, new: String },
 
-    #[error("Invalid name format: '{name}' contains a colon character (:) which is not allowed")]
-    InvalidNameFormat { name: String },
+    #[error("Invalid name format: '{name}' {reason}")]
+    InvalidNameFormat { name: String, reason: String },
+
+    #[error("Name already exists: {name}")]
+    NameAlreadyExists { name: String },
 
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("Invalid handle: '{handle}' {reason}")]
+    InvalidHandle { handle: String, reason: String },
+
+    #[error("Handle already taken: {handle}")]
+    HandleTaken { handle: String },
+
+    #[error("Handle not found: {handle}")]
+    HandleNotFound { handle: String },
+
+    #[error("Not an Account: {fid}")]
+    NotAnAccount { fid: String },
+
+    #[error("Invalid handle fee: {reason}")]
+    InvalidHandleFee { reason: String },
+
+    #[error("Insufficient fee: required {required}")]
+    InsufficientFee { required: String },
+
+    #[error("No pending ownership transfer: {fid}")]
+    NoPendingTransfer { fid: String },
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -71,3 +126,4 @@ impl From<semver::Error> for ContractError {
         Self::SemVer(err.to_string())
     }
 }
+
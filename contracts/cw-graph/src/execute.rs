@@ -1,21 +1,26 @@
+// ContractError::TypeConflict carries the full type constraints and is matched on by clients
+#![allow(clippy::result_large_err)]
+
 use crate::contract::map_validate;
 use crate::error::ContractError;
-use crate::msg::Cyberlink;
-use crate::state::{cyberlinks, CyberlinkState, CONFIG, DELETED_GIDS, GID, NAMED_CYBERLINKS, TYPE_GIDS, OWNER_LINK_COUNT, TYPE_LINK_COUNT, OWNER_TYPE_LINK_COUNT};
-use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, Storage, Addr, StdResult};
-
+use crate::msg::{Cyberlink, ImportSubgraphResponse, SemcoreEntry};
+use crate::semcores::base_type_name;
+use crate::state::{cyberlinks, Change, ChangeOp, CyberlinkState, CHANGES, CHANGE_SEQ, Handle, HandleFee, NodeAcl, NODE_ACL, TypeKind, TypeLifecycle, TypeMeta, CONFIG, handles, PENDING_TRANSFERS, TYPES, DELETED_GIDS, tombstones, Tombstone, GID, NAMED_CYBERLINKS, TYPE_GIDS, OWNER_LINK_COUNT, TYPE_LINK_COUNT, OWNER_TYPE_LINK_COUNT, IN_DEGREE, OUT_DEGREE, IN_DEGREE_BY_TYPE, OUT_DEGREE_BY_TYPE, REACTION_TALLIES, OWNER_REACTIONS, FIELD_INDEX, IMPORTED_FIDS, rank_scores, RankNode, RankPhase, RankRun, RankScore, RANK_CURRENT, RANK_NEXT, RANK_NODES, RANK_RUN};
+use cw_storage_plus::{Bound, Map};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use cosmwasm_std::{coins, to_json_binary, Attribute, BankMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Response, Storage, Addr, StdResult, Timestamp};
+use cw_utils::must_pay;
+
+// Returns a warning attribute when the cyberlink uses a deprecated type
 fn validate_cyberlink(
     deps: Deps,
     cyberlink: Cyberlink
-) -> Result<(), ContractError> {
-    // Validation
-    if cyberlink.from != cyberlink.to && (cyberlink.from.is_none() || cyberlink.to.is_none()) {
-        return Err(ContractError::InvalidCyberlink {
-            from: cyberlink.from.unwrap_or_else(|| "None".to_string()),
-            to: cyberlink.to.unwrap_or_else(|| "None".to_string()),
-            type_: cyberlink.type_.clone(),
-        });
-    }
+) -> Result<Option<Attribute>, ContractError> {
+    // Validation of the node/edge shape declared by the type
+    let rules = type_rules(deps.storage, &cyberlink.type_)?;
+    let warning = check_lifecycle(&rules, &cyberlink.type_)?;
+    check_shape(&rules, &cyberlink.type_, cyberlink.from.as_deref(), cyberlink.to.as_deref())?;
 
     let (mut dfrom, mut dto): (Option<CyberlinkState>, Option<CyberlinkState>) = (None, None);
 
@@ -23,6 +28,9 @@ fn validate_cyberlink(
     if dtype_id.is_none() {
         return Err(ContractError::TypeNotExists { type_: cyberlink.type_.clone() });
     }
+    if DELETED_GIDS.has(deps.storage, dtype_id.unwrap()) {
+        return Err(ContractError::DeletedCyberlink { fid: cyberlink.type_.clone() });
+    }
     let dtype = cyberlinks().load(deps.storage, dtype_id.unwrap()).unwrap();
 
     if cyberlink.from.is_some() {
@@ -30,6 +38,9 @@ fn validate_cyberlink(
         if dfrom_id.is_none() {
             return Err(ContractError::FromNotExists { from: cyberlink.from.clone().unwrap() });
         }
+        if DELETED_GIDS.has(deps.storage, dfrom_id.unwrap()) {
+            return Err(ContractError::DeletedCyberlink { fid: cyberlink.from.clone().unwrap() });
+        }
         dfrom = cyberlinks().may_load(deps.storage, dfrom_id.unwrap()).unwrap();
     }
     if cyberlink.to.is_some() {
@@ -37,6 +48,9 @@ fn validate_cyberlink(
         if dto_id.is_none() {
             return Err(ContractError::ToNotExists { to: cyberlink.to.clone().unwrap() });
         }
+        if DELETED_GIDS.has(deps.storage, dto_id.unwrap()) {
+            return Err(ContractError::DeletedCyberlink { fid: cyberlink.to.clone().unwrap() });
+        }
         dto = cyberlinks().may_load(deps.storage, dto_id.unwrap()).unwrap();
     }
 
@@ -71,7 +85,7 @@ fn validate_cyberlink(
         }
     }
 
-    Ok(())
+    Ok(warning)
 }
 
 fn create_cyberlink(
@@ -85,17 +99,19 @@ fn create_cyberlink(
     let id = GID.load(deps.storage)? + 1;
     GID.save(deps.storage, &id)?;
 
-    let formatted_id: String;
-    if name.is_none() {
-        // Get and increment the type-specific ID
-        let type_id = TYPE_GIDS.may_load(deps.storage, cyberlink.type_.as_str())?.unwrap_or(0) + 1;
-        TYPE_GIDS.save(deps.storage, cyberlink.type_.as_str(), &type_id)?;
-
-        // Generate the formatted ID string (e.g., "post:42")
-        formatted_id = format!("{}:{}", cyberlink.type_, type_id);
-    } else {
-        formatted_id = name.unwrap();
-    }
+    let named = name.is_some();
+    let inferred_kind = TypeKind::infer(&cyberlink.from, &cyberlink.to);
+    let formatted_id = match name {
+        Some(name) => name,
+        None => {
+            // Get and increment the type-specific ID
+            let type_id = TYPE_GIDS.may_load(deps.storage, cyberlink.type_.as_str())?.unwrap_or(0) + 1;
+            TYPE_GIDS.save(deps.storage, cyberlink.type_.as_str(), &type_id)?;
+
+            // Generate the formatted ID string (e.g., "post:42")
+            format!("{}:{}", cyberlink.type_, type_id)
+        }
+    };
 
 
     // Save new Cyberlink
@@ -107,17 +123,35 @@ fn create_cyberlink(
         owner: info.sender.clone(),
         created_at: env.block.time,
         updated_at: None,
+        deleted_at: None,
         fid: Some(formatted_id.clone()),
     };
 
+    // One reaction per owner and target
+    add_reaction(deps.storage, id, &cyberlink_state)?;
+
     // Also save the cyberlink with its string ID for direct access
     NAMED_CYBERLINKS.save(deps.storage, formatted_id.as_str(), &id)?;
 
     // Save the cyberlink using IndexedMap with numeric ID for efficient indexing
     cyberlinks().save(deps.storage, id, &cyberlink_state)?;
+    record_change(deps.storage, id, ChangeOp::Create, env.block.time)?;
+
+    // Named type definitions become part of the type registry
+    if named && cyberlink_state.type_ == "Type" {
+        register_type(deps.storage, &formatted_id, TypeMeta {
+            semcore: None,
+            kind: inferred_kind,
+            allow_self_loop: false,
+            lifecycle: TypeLifecycle::Active,
+            indexed_fields: vec![],
+        })?;
+    }
 
     // ---- Increment Counters ----
     increment_stats(deps.storage, &cyberlink_state.owner, &cyberlink_state.type_)?;
+    increment_degrees(deps.storage, &cyberlink_state)?;
+    index_fields(deps.storage, id, &cyberlink_state)?;
     // -------------------------
 
     Ok((id, formatted_id))
@@ -136,13 +170,11 @@ pub fn execute_create_named_cyberlink(
         return Err(ContractError::Unauthorized {});
     }
 
-    // Validate name doesn't contain colons
-    if name.contains(':') {
-        return Err(ContractError::InvalidNameFormat { name });
-    }
+    // Validate name doesn't contain reserved characters
+    validate_name(&name)?;
 
     // Validate the cyberlink
-    validate_cyberlink(deps.as_ref(), cyberlink.clone())?;
+    let warning = validate_cyberlink(deps.as_ref(), cyberlink.clone())?;
 
     // Create the cyberlink
     let (numeric_id, formatted_id) = create_cyberlink(deps, env, info, Some(name), cyberlink.clone())?;
@@ -152,6 +184,41 @@ pub fn execute_create_named_cyberlink(
         .add_attribute("gid", numeric_id.to_string())
         .add_attribute("fid", formatted_id)
         .add_attribute("type", cyberlink.type_)
+        .add_attributes(warning)
+    )
+}
+
+pub fn execute_create_owned_named_cyberlink(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    cyberlink: Cyberlink,
+) -> Result<Response, ContractError> {
+    // Types are defined by admins only
+    if cyberlink.type_ == "Type" {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Names are reserved under the sender's namespace, e.g. "<address>/profile"
+    validate_name(&name)?;
+    let owned_name = format!("{}{}{}", info.sender, OWNER_NAMESPACE_SEPARATOR, name);
+    if NAMED_CYBERLINKS.has(deps.storage, &owned_name) {
+        return Err(ContractError::NameAlreadyExists { name: owned_name });
+    }
+
+    // Validate the cyberlink
+    let warning = validate_cyberlink(deps.as_ref(), cyberlink.clone())?;
+
+    // Create the cyberlink
+    let (numeric_id, formatted_id) = create_cyberlink(deps, env, info, Some(owned_name), cyberlink.clone())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_cyberlink")
+        .add_attribute("gid", numeric_id.to_string())
+        .add_attribute("fid", formatted_id)
+        .add_attribute("type", cyberlink.type_)
+        .add_attributes(warning)
     )
 }
 
@@ -162,13 +229,13 @@ pub fn execute_create_cyberlink(
     cyberlink: Cyberlink
 ) -> Result<Response, ContractError> {
     // Check if the user is an executor
-    let config = CONFIG.load(deps.storage)?;
+    let _config = CONFIG.load(deps.storage)?;
     // if !config.can_execute(info.sender.as_str()) {
     //     return Err(ContractError::Unauthorized {});
     // }
 
     // Validate the cyberlink
-    validate_cyberlink(deps.as_ref(), cyberlink.clone())?;
+    let warning = validate_cyberlink(deps.as_ref(), cyberlink.clone())?;
 
     // Create the cyberlink
     let (numeric_id, formatted_id) = create_cyberlink(deps, env, info, None, cyberlink.clone())?;
@@ -178,6 +245,7 @@ pub fn execute_create_cyberlink(
         .add_attribute("type", cyberlink.type_)
         .add_attribute("gid", numeric_id.to_string())
         .add_attribute("fid", formatted_id)
+        .add_attributes(warning)
     )
 }
 
@@ -188,17 +256,22 @@ pub fn execute_create_cyberlinks(
     cyberlinks: Vec<Cyberlink>
 ) -> Result<Response, ContractError> {
     // Check if the user is an executor
-    let config = CONFIG.load(deps.storage)?;
+    let _config = CONFIG.load(deps.storage)?;
     // if !config.can_execute(info.sender.as_str()) {
     //     return Err(ContractError::Unauthorized {});
     // }
 
     let mut gids = Vec::with_capacity(cyberlinks.len());
     let mut fids = Vec::with_capacity(cyberlinks.len());
+    let mut warnings: Vec<Attribute> = vec![];
     
     for cyberlink in cyberlinks {
         // Validate the cyberlink
-        validate_cyberlink(deps.as_ref(), cyberlink.clone())?;
+        if let Some(warning) = validate_cyberlink(deps.as_ref(), cyberlink.clone())? {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
 
         // Create the cyberlink (this now increments counters internally)
         let (gid, fid) = create_cyberlink(deps.branch(), env.clone(), info.clone(), None, cyberlink)?;
@@ -211,6 +284,82 @@ pub fn execute_create_cyberlinks(
         .add_attribute("count", gids.len().to_string())
         .add_attribute("gids", gids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(","))
         .add_attribute("fids", fids.join(","))
+        .add_attributes(warnings)
+    )
+}
+
+pub fn execute_import_subgraph(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    import: String,
+    entries: Vec<SemcoreEntry>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut mapping = Vec::with_capacity(entries.len());
+    let mut created = 0;
+    let mut warnings: Vec<Attribute> = vec![];
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        // Entries are tracked by their id, which makes resending a chunk safe
+        let Some(id) = entry.id.clone() else {
+            return Err(ContractError::MissingImportId { index });
+        };
+        if let Some(fid) = IMPORTED_FIDS.may_load(deps.storage, (&info.sender, &import, &id))? {
+            mapping.push((id, fid));
+            continue;
+        }
+
+        // Types keep their name, they are reused when already registered and otherwise
+        // defined by admins only, like named cyberlinks
+        let name = if entry.type_ == "Type" {
+            if TYPES.has(deps.storage, &id) {
+                IMPORTED_FIDS.save(deps.storage, (&info.sender, &import, &id), &id)?;
+                mapping.push((id.clone(), id));
+                continue;
+            }
+            if !config.can_modify(info.sender.as_str()) {
+                return Err(ContractError::Unauthorized {});
+            }
+            validate_name(&id)?;
+            Some(id.clone())
+        } else {
+            None
+        };
+
+        // References to entries outside of the import are kept as they are
+        let remap = |fid: Option<String>| -> StdResult<Option<String>> {
+            let Some(fid) = fid else {
+                return Ok(None);
+            };
+            let imported = IMPORTED_FIDS.may_load(deps.storage, (&info.sender, &import, &fid))?;
+            Ok(Some(imported.unwrap_or(fid)))
+        };
+        let cyberlink = Cyberlink {
+            type_: entry.type_.clone(),
+            from: remap(entry.from.clone())?,
+            to: remap(entry.to.clone())?,
+            value: entry.value_string(),
+        };
+
+        if let Some(warning) = validate_cyberlink(deps.as_ref(), cyberlink.clone())? {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+        let (_, fid) = create_cyberlink(deps.branch(), env.clone(), info.clone(), name, cyberlink)?;
+        created += 1;
+
+        IMPORTED_FIDS.save(deps.storage, (&info.sender, &import, &id), &fid)?;
+        mapping.push((id, fid));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "import_subgraph")
+        .add_attribute("import", import)
+        .add_attribute("count", created.to_string())
+        .add_attributes(warnings)
+        .set_data(to_json_binary(&ImportSubgraphResponse { mapping })?)
     )
 }
 
@@ -233,18 +382,29 @@ pub fn execute_update_cyberlink(
 
     let config = CONFIG.load(deps.storage)?;
 
-    // Check if the user is the owner or an admin
-    if old_cyberlink_state.owner != info.sender && !config.is_admin(info.sender.as_str()) {
+    // Check if the user is the owner, an editor of the node or an admin
+    if old_cyberlink_state.owner != info.sender
+        && !node_acl(deps.storage, gid)?.is_editor(&info.sender)
+        && !config.is_admin(info.sender.as_str()) {
         return Err(ContractError::Unauthorized {});
     }
 
     // Update the state and save
-    cyberlinks().update(deps.storage, gid, |old_opt| -> Result<CyberlinkState, ContractError> {
+    let new_cyberlink_state = cyberlinks().update(deps.storage, gid, |old_opt| -> Result<CyberlinkState, ContractError> {
         let mut state = old_opt.ok_or_else(|| ContractError::NotFound { fid: fid.clone() })?;
         state.value = new_value.unwrap_or_default(); // Update value
         state.updated_at = Some(env.block.time); // Set updated time
         Ok(state)
     })?;
+    record_change(deps.storage, gid, ChangeOp::Update, env.block.time)?;
+    unindex_fields(deps.storage, gid, &old_cyberlink_state)?;
+    index_fields(deps.storage, gid, &new_cyberlink_state)?;
+
+    // A changed reaction moves to the tally of its new value
+    if is_reaction(&old_cyberlink_state) && old_cyberlink_state.value != new_cyberlink_state.value {
+        adjust_tally(deps.storage, &old_cyberlink_state.to, &old_cyberlink_state.value, false)?;
+        adjust_tally(deps.storage, &new_cyberlink_state.to, &new_cyberlink_state.value, true)?;
+    }
 
     Ok(Response::new()
         .add_attribute("action", "update_cyberlink")
@@ -255,7 +415,7 @@ pub fn execute_update_cyberlink(
 
 pub fn execute_delete_cyberlink(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     fid: String // Formatted ID (e.g., "Type:1")
 ) -> Result<Response, ContractError> {
@@ -264,7 +424,7 @@ pub fn execute_delete_cyberlink(
 
     // Check if already marked as deleted
     if DELETED_GIDS.has(deps.storage, gid) {
-        return Err(ContractError::DeletedCyberlink { fid: fid });
+        return Err(ContractError::DeletedCyberlink { fid });
     }
 
     // Load the cyberlink state to check ownership and get details for counter decrement
@@ -272,23 +432,37 @@ pub fn execute_delete_cyberlink(
 
     let config = CONFIG.load(deps.storage)?;
 
-    // Check if the user is the owner or an admin
-    if cyberlink_state.owner != info.sender && !config.is_admin(info.sender.as_str()) {
+    // Check if the user is the owner, a deleter of the node or an admin
+    if cyberlink_state.owner != info.sender
+        && !node_acl(deps.storage, gid)?.is_deleter(&info.sender)
+        && !config.is_admin(info.sender.as_str()) {
         return Err(ContractError::Unauthorized {});
     }
 
     // ---- Decrement Counters ----
     decrement_stats(deps.storage, &cyberlink_state.owner, &cyberlink_state.type_)?;
+    decrement_degrees(deps.storage, &cyberlink_state)?;
+    remove_reaction(deps.storage, &cyberlink_state)?;
+    unindex_fields(deps.storage, gid, &cyberlink_state)?;
     // -------------------------
 
-    // Mark the cyberlink as deleted using the DELETED_IDS map
+    // Mark the cyberlink as deleted using the DELETED_IDS map,
+    // the state stays in place (out of the index queries) until it is restored or purged
     DELETED_GIDS.save(deps.storage, gid, &true)?;
+    let deleted_state = CyberlinkState {
+        deleted_at: Some(env.block.time),
+        ..cyberlink_state.clone()
+    };
+    cyberlinks().replace(deps.storage, gid, Some(&deleted_state), Some(&cyberlink_state))?;
+    record_change(deps.storage, gid, ChangeOp::Delete, env.block.time)?;
+    tombstones().save(deps.storage, gid, &Tombstone {
+        deleted_at: env.block.time,
+        deleted_by: info.sender.clone(),
+    })?;
 
-    // Optional: Completely remove the cyberlink state and its named entry to save space
-    cyberlinks().remove(deps.storage, gid)?;
-    // NAMED_CYBERLINKS.remove(deps.storage, id.as _str());
-    // Consider the implications: Queries by GID will fail entirely instead of returning a "deleted" error.
-    // Queries relying on the existence of the NAMED_CYBERLINKS entry will also fail.
+    // A deleted cyberlink can't be accepted anymore, nor be resolved by a handle
+    PENDING_TRANSFERS.remove(deps.storage, gid);
+    release_handles(deps.storage, &fid)?;
 
     Ok(Response::new()
         .add_attribute("action", "delete_cyberlink")
@@ -297,6 +471,248 @@ pub fn execute_delete_cyberlink(
     )
 }
 
+pub fn execute_restore_cyberlink(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fid: String,
+) -> Result<Response, ContractError> {
+    let gid = NAMED_CYBERLINKS.may_load(deps.storage, &fid)?.ok_or_else(|| ContractError::NotFound { fid: fid.clone() })?;
+    if !DELETED_GIDS.has(deps.storage, gid) {
+        return Err(ContractError::NotDeleted { fid });
+    }
+
+    // Cyberlinks deleted before tombstones were introduced have no state left to restore
+    let cyberlink_state = cyberlinks().may_load(deps.storage, gid)?.ok_or_else(|| ContractError::NotFound { fid: fid.clone() })?;
+
+    // Check if the user is the owner or an admin
+    let config = CONFIG.load(deps.storage)?;
+    if cyberlink_state.owner != info.sender && !config.is_admin(info.sender.as_str()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // The endpoints may have been deleted or purged in the meantime
+    match NAMED_CYBERLINKS.may_load(deps.storage, &cyberlink_state.from)? {
+        None => return Err(ContractError::FromNotExists { from: cyberlink_state.from }),
+        Some(from_gid) if DELETED_GIDS.has(deps.storage, from_gid) => return Err(ContractError::DeletedCyberlink { fid: cyberlink_state.from }),
+        Some(_) => {}
+    }
+    match NAMED_CYBERLINKS.may_load(deps.storage, &cyberlink_state.to)? {
+        None => return Err(ContractError::ToNotExists { to: cyberlink_state.to }),
+        Some(to_gid) if DELETED_GIDS.has(deps.storage, to_gid) => return Err(ContractError::DeletedCyberlink { fid: cyberlink_state.to }),
+        Some(_) => {}
+    }
+
+    // The owner may have reacted to the same target again in the meantime
+    add_reaction(deps.storage, gid, &cyberlink_state)?;
+
+    DELETED_GIDS.remove(deps.storage, gid);
+    tombstones().remove(deps.storage, gid)?;
+    let restored_state = CyberlinkState {
+        deleted_at: None,
+        ..cyberlink_state.clone()
+    };
+    cyberlinks().replace(deps.storage, gid, Some(&restored_state), Some(&cyberlink_state))?;
+    record_change(deps.storage, gid, ChangeOp::Restore, env.block.time)?;
+    increment_stats(deps.storage, &cyberlink_state.owner, &cyberlink_state.type_)?;
+    increment_degrees(deps.storage, &cyberlink_state)?;
+    index_fields(deps.storage, gid, &cyberlink_state)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "restore_cyberlink")
+        .add_attribute("gid", gid.to_string())
+        .add_attribute("fid", fid)
+    )
+}
+
+pub fn execute_purge_cyberlink(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fid: String,
+) -> Result<Response, ContractError> {
+    let gid = NAMED_CYBERLINKS.may_load(deps.storage, &fid)?.ok_or_else(|| ContractError::NotFound { fid: fid.clone() })?;
+
+    // Only deleted cyberlinks can be purged, the counters were already updated on delete
+    if !DELETED_GIDS.has(deps.storage, gid) {
+        return Err(ContractError::NotDeleted { fid });
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    match cyberlinks().may_load(deps.storage, gid)? {
+        Some(cyberlink_state) => {
+            // Check if the user is the owner or an admin
+            if cyberlink_state.owner != info.sender && !config.is_admin(info.sender.as_str()) {
+                return Err(ContractError::Unauthorized {});
+            }
+            // Links that still point at the cyberlink would be left dangling
+            if let Some(by) = live_reference(deps.storage, &fid)? {
+                return Err(ContractError::StillReferenced { fid, by });
+            }
+            cyberlinks().remove(deps.storage, gid)?;
+            if cyberlink_state.type_ == "Type" {
+                TYPES.remove(deps.storage, &fid);
+            }
+        }
+        // Cyberlinks deleted before tombstones were introduced lost their owner, only admins clean them up
+        None => {
+            if !config.is_admin(info.sender.as_str()) {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+    }
+
+    // Free the name and drop everything else kept for the gid
+    NAMED_CYBERLINKS.remove(deps.storage, &fid);
+    DELETED_GIDS.remove(deps.storage, gid);
+    tombstones().remove(deps.storage, gid)?;
+    NODE_ACL.remove(deps.storage, gid);
+    PENDING_TRANSFERS.remove(deps.storage, gid);
+    rank_scores().remove(deps.storage, &fid)?;
+    release_handles(deps.storage, &fid)?;
+    record_change(deps.storage, gid, ChangeOp::Purge, env.block.time)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "purge_cyberlink")
+        .add_attribute("gid", gid.to_string())
+        .add_attribute("fid", fid)
+    )
+}
+
+pub fn execute_transfer_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fid: String,
+    new_owner: String,
+    require_accept: Option<bool>,
+) -> Result<Response, ContractError> {
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    let require_accept = require_accept.unwrap_or(false);
+
+    let gid = transfer_or_propose(deps.storage, &info.sender, &fid, &new_owner, require_accept, env.block.time)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_ownership")
+        .add_attribute("gid", gid.to_string())
+        .add_attribute("fid", fid)
+        .add_attribute("new_owner", new_owner)
+        .add_attribute("pending", require_accept.to_string())
+    )
+}
+
+pub fn execute_transfer_ownerships(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fids: Vec<String>,
+    new_owner: String,
+    require_accept: Option<bool>,
+) -> Result<Response, ContractError> {
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    let require_accept = require_accept.unwrap_or(false);
+
+    // Any failure reverts the whole batch
+    let mut gids = Vec::with_capacity(fids.len());
+    for fid in &fids {
+        gids.push(transfer_or_propose(deps.storage, &info.sender, fid, &new_owner, require_accept, env.block.time)?.to_string());
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_ownerships")
+        .add_attribute("gids", gids.join(","))
+        .add_attribute("count", fids.len().to_string())
+        .add_attribute("new_owner", new_owner)
+        .add_attribute("pending", require_accept.to_string())
+    )
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fid: String,
+) -> Result<Response, ContractError> {
+    let gid = NAMED_CYBERLINKS.may_load(deps.storage, &fid)?.ok_or_else(|| ContractError::NotFound { fid: fid.clone() })?;
+    let pending = PENDING_TRANSFERS.may_load(deps.storage, gid)?.ok_or_else(|| ContractError::NoPendingTransfer { fid: fid.clone() })?;
+    if pending != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let cyberlink_state = cyberlinks().load(deps.storage, gid)?;
+    transfer_cyberlink(deps.storage, gid, cyberlink_state, &info.sender, env.block.time)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("gid", gid.to_string())
+        .add_attribute("fid", fid)
+        .add_attribute("new_owner", info.sender)
+    )
+}
+
+pub fn execute_cancel_ownership_transfer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fid: String,
+) -> Result<Response, ContractError> {
+    let gid = NAMED_CYBERLINKS.may_load(deps.storage, &fid)?.ok_or_else(|| ContractError::NotFound { fid: fid.clone() })?;
+    if !PENDING_TRANSFERS.has(deps.storage, gid) {
+        return Err(ContractError::NoPendingTransfer { fid });
+    }
+
+    // Only the current owner can withdraw the offer, the recipient can simply ignore it
+    let cyberlink_state = cyberlinks().load(deps.storage, gid)?;
+    if cyberlink_state.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    PENDING_TRANSFERS.remove(deps.storage, gid);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_ownership_transfer")
+        .add_attribute("gid", gid.to_string())
+        .add_attribute("fid", fid)
+    )
+}
+
+pub fn execute_set_acl(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fid: String,
+    editors: Vec<String>,
+    deleters: Vec<String>,
+) -> Result<Response, ContractError> {
+    let gid = NAMED_CYBERLINKS.may_load(deps.storage, &fid)?.ok_or_else(|| ContractError::NotFound { fid: fid.clone() })?;
+    if DELETED_GIDS.has(deps.storage, gid) {
+        return Err(ContractError::DeletedCyberlink { fid });
+    }
+
+    // The ACL is managed by the owner only
+    let cyberlink_state = cyberlinks().load(deps.storage, gid)?;
+    if cyberlink_state.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let acl = NodeAcl {
+        editors: map_validate(deps.api, &editors)?,
+        deleters: map_validate(deps.api, &deleters)?,
+    };
+    if acl.editors.is_empty() && acl.deleters.is_empty() {
+        NODE_ACL.remove(deps.storage, gid);
+    } else {
+        NODE_ACL.save(deps.storage, gid, &acl)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_acl")
+        .add_attribute("gid", gid.to_string())
+        .add_attribute("fid", fid)
+        .add_attribute("editors", acl.editors.len().to_string())
+        .add_attribute("deleters", acl.deleters.len().to_string())
+    )
+}
+
 pub fn execute_update_admins(
     deps: DepsMut,
     _env: Env,
@@ -345,41 +761,825 @@ pub fn execute_update_executors(
     )
 }
 
-// --- Counter Helper Functions ---
+pub fn execute_update_type_rules(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    type_: String,
+    kind: Option<TypeKind>,
+    allow_self_loop: Option<bool>,
+    indexed_fields: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    // Check if the user is an admin
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(info.sender.as_str()) {
+        return Err(ContractError::Unauthorized {});
+    }
 
-fn increment_stats(
-    storage: &mut dyn Storage,
-    owner: &Addr,
-    type_: &str,
-) -> StdResult<()> {
-    // Increment owner count
-    let owner_count = OWNER_LINK_COUNT.may_load(storage, owner)?.unwrap_or(0) + 1;
-    OWNER_LINK_COUNT.save(storage, owner, &owner_count)?;
+    let mut meta = TYPES.may_load(deps.storage, &type_)?
+        .ok_or_else(|| ContractError::TypeNotExists { type_: type_.clone() })?;
+    if let Some(kind) = kind {
+        meta.kind = kind;
+    }
+    if let Some(allow_self_loop) = allow_self_loop {
+        meta.allow_self_loop = allow_self_loop;
+    }
+    if let Some(indexed_fields) = indexed_fields {
+        // Cyberlinks created before the change would be missing from the field index
+        let has_cyberlinks = cyberlinks().idx.type_.prefix(type_.clone())
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        if has_cyberlinks && indexed_fields != meta.indexed_fields {
+            return Err(ContractError::IndexedFieldsInUse { type_ });
+        }
+        meta.indexed_fields = indexed_fields;
+    }
+    TYPES.save(deps.storage, &type_, &meta)?;
 
-    // Increment type count
-    let type_count = TYPE_LINK_COUNT.may_load(storage, type_)?.unwrap_or(0) + 1;
-    TYPE_LINK_COUNT.save(storage, type_, &type_count)?;
+    Ok(Response::new()
+        .add_attribute("action", "update_type_rules")
+        .add_attribute("type", type_)
+    )
+}
 
-    // Increment owner-type count
-    let owner_type_count = OWNER_TYPE_LINK_COUNT.may_load(storage, (owner, type_))?.unwrap_or(0) + 1;
-    OWNER_TYPE_LINK_COUNT.save(storage, (owner, type_), &owner_type_count)?;
+pub fn execute_set_type_lifecycle(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    type_: String,
+    lifecycle: TypeLifecycle,
+) -> Result<Response, ContractError> {
+    // Check if the user is an admin
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(info.sender.as_str()) {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    Ok(())
+    let mut meta = TYPES.may_load(deps.storage, &type_)?
+        .ok_or_else(|| ContractError::TypeNotExists { type_: type_.clone() })?;
+    meta.lifecycle = lifecycle;
+    TYPES.save(deps.storage, &type_, &meta)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_type_lifecycle")
+        .add_attribute("type", type_)
+    )
 }
 
-fn decrement_stats(
-    storage: &mut dyn Storage,
+pub fn execute_update_handle_fee(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fee: Option<HandleFee>,
+) -> Result<Response, ContractError> {
+    // Load config
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Check if the user is an admin
+    if !config.is_admin(info.sender.as_str()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // The fee doubles for every character below the base length, it must fit for the shortest handles
+    if let Some(fee) = &fee {
+        fee.for_handle(&"_".repeat(HANDLE_MIN_LENGTH)).map_err(|_| ContractError::InvalidHandleFee { reason: "overflows for short handles".to_string() })?;
+    }
+    config.handle_fee = fee;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_handle_fee")
+    )
+}
+
+pub fn execute_claim_handle(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    handle: String,
+    account: String,
+) -> Result<Response, ContractError> {
+    let handle = validate_handle(&handle)?;
+
+    // The handle must point to an Account of the sender
+    let gid = NAMED_CYBERLINKS.may_load(deps.storage, &account)?.ok_or_else(|| ContractError::NotFound { fid: account.clone() })?;
+    if DELETED_GIDS.has(deps.storage, gid) {
+        return Err(ContractError::DeletedCyberlink { fid: account });
+    }
+    let account_state = cyberlinks().load(deps.storage, gid)?;
+    if base_type_name(&account_state.type_) != "Account" {
+        return Err(ContractError::NotAnAccount { fid: account });
+    }
+    if account_state.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut refund = None;
+    match handles().may_load(deps.storage, &handle)? {
+        // The owner of a handle can point it to another Account for free
+        Some(existing) if existing.owner == info.sender => {}
+        Some(_) => return Err(ContractError::HandleTaken { handle }),
+        None => {
+            let config = CONFIG.load(deps.storage)?;
+            if let Some(fee) = config.handle_fee {
+                let required = fee.for_handle(&handle).map_err(|_| ContractError::InvalidHandleFee { reason: "overflows for short handles".to_string() })?;
+                let paid = must_pay(&info, &fee.denom)?;
+                if paid < required {
+                    return Err(ContractError::InsufficientFee { required: format!("{}{}", required, fee.denom) });
+                }
+                // Anything paid above the fee goes back to the sender
+                if paid > required {
+                    refund = Some(BankMsg::Send { to_address: info.sender.to_string(), amount: coins((paid - required).u128(), fee.denom) });
+                }
+            }
+        }
+    }
+
+    handles().save(deps.storage, &handle, &Handle { owner: info.sender.clone(), account: account.clone() })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_handle")
+        .add_attribute("handle", handle)
+        .add_attribute("account", account)
+        .add_messages(refund)
+    )
+}
+
+pub fn execute_release_handle(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    handle: String,
+) -> Result<Response, ContractError> {
+    let handle = handle.to_lowercase();
+    let existing = handles().may_load(deps.storage, &handle)?.ok_or_else(|| ContractError::HandleNotFound { handle: handle.clone() })?;
+    if existing.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    handles().remove(deps.storage, &handle)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "release_handle")
+        .add_attribute("handle", handle)
+    )
+}
+
+pub fn execute_transfer_handle(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    handle: String,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+
+    let handle = handle.to_lowercase();
+    let mut existing = handles().may_load(deps.storage, &handle)?.ok_or_else(|| ContractError::HandleNotFound { handle: handle.clone() })?;
+    if existing.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // The handle keeps resolving to the same Account until the new owner claims it for another one
+    existing.owner = new_owner.clone();
+    handles().save(deps.storage, &handle, &existing)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_handle")
+        .add_attribute("handle", handle)
+        .add_attribute("new_owner", new_owner)
+    )
+}
+
+// Settings for ranking runs
+const DEFAULT_RANK_DAMPING_PERCENT: u64 = 85;
+const DEFAULT_RANK_ITERATIONS: u32 = 20;
+const MAX_RANK_ITERATIONS: u32 = 100;
+const DEFAULT_RANK_STEPS: u32 = 200;
+const MAX_RANK_STEPS: u32 = 2000;
+
+pub fn execute_start_ranking(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    link_types: Vec<String>,
+    damping: Option<Decimal>,
+    iterations: Option<u32>,
+) -> Result<Response, ContractError> {
+    // Check if the user is an admin
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(info.sender.as_str()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if link_types.is_empty() {
+        return Err(ContractError::InvalidRanking { reason: "no link types".to_string() });
+    }
+    for type_ in &link_types {
+        if !TYPES.has(deps.storage, type_) {
+            return Err(ContractError::TypeNotExists { type_: type_.clone() });
+        }
+    }
+    let damping = damping.unwrap_or(Decimal::percent(DEFAULT_RANK_DAMPING_PERCENT));
+    if damping >= Decimal::one() {
+        return Err(ContractError::InvalidRanking { reason: "damping must be below 1".to_string() });
+    }
+    let iterations = iterations.unwrap_or(DEFAULT_RANK_ITERATIONS);
+    if iterations == 0 || iterations > MAX_RANK_ITERATIONS {
+        return Err(ContractError::InvalidRanking { reason: format!("iterations must be between 1 and {}", MAX_RANK_ITERATIONS) });
+    }
+
+    // A run in progress is abandoned, its working data is cleared by the new one
+    let max_gid = GID.load(deps.storage)?;
+    RANK_RUN.save(deps.storage, &RankRun {
+        link_types,
+        damping,
+        iterations,
+        iteration: 0,
+        phase: RankPhase::Clear,
+        max_gid,
+        type_index: 0,
+        link_cursor: None,
+        node_cursor: None,
+        node_count: 0,
+        linked_count: 0,
+        dangling: Decimal::zero(),
+        next_dangling: Decimal::zero(),
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_ranking")
+    )
+}
+
+pub fn execute_continue_ranking(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    // Check if the user is an admin
+    let config = CONFIG.load(deps.storage)?;
+    if !config.is_admin(info.sender.as_str()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut run = RANK_RUN.may_load(deps.storage)?.ok_or(ContractError::NoRankingRun {})?;
+    if run.phase == RankPhase::Done {
+        return Err(ContractError::NoRankingRun {});
+    }
+
+    // Every step reads or writes a single link or node; a phase that ends early hands over the rest
+    let budget = limit.unwrap_or(DEFAULT_RANK_STEPS).clamp(1, MAX_RANK_STEPS) as usize;
+    let mut used = 0;
+    while used < budget && run.phase != RankPhase::Done {
+        used += rank_step(deps.storage, &mut run, budget - used)?;
+    }
+    RANK_RUN.save(deps.storage, &run)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "continue_ranking")
+        .add_attribute("phase", format!("{:?}", run.phase).to_lowercase())
+        .add_attribute("iteration", run.iteration.to_string())
+        .add_attribute("steps", used.to_string())
+    )
+}
+
+// --- Name Helper Functions ---
+
+// Separates the owner address from the name in owner-namespaced names
+pub const OWNER_NAMESPACE_SEPARATOR: char = '/';
+
+// Names can't be empty or contain the FID (:) and owner namespace (/) separators
+fn validate_name(name: &str) -> Result<(), ContractError> {
+    let reason = if name.is_empty() {
+        "is empty"
+    } else if name.contains(':') {
+        "contains a colon character (:) which is not allowed"
+    } else if name.contains(OWNER_NAMESPACE_SEPARATOR) {
+        "contains a slash character (/) which is reserved for owner namespaces"
+    } else {
+        return Ok(());
+    };
+
+    Err(ContractError::InvalidNameFormat { name: name.to_string(), reason: reason.to_string() })
+}
+
+// --- Handle Helper Functions ---
+
+pub const HANDLE_MIN_LENGTH: usize = 3;
+pub const HANDLE_MAX_LENGTH: usize = 30;
+const RESERVED_HANDLES: [&str; 7] = ["admin", "root", "system", "cyber", "null", "any", "type"];
+
+// Handles are case-insensitive, returns the lowercase form used as the registry key
+fn validate_handle(handle: &str) -> Result<String, ContractError> {
+    let normalized = handle.to_lowercase();
+    let reason = if normalized.len() < HANDLE_MIN_LENGTH || normalized.len() > HANDLE_MAX_LENGTH {
+        format!("must be between {} and {} characters long", HANDLE_MIN_LENGTH, HANDLE_MAX_LENGTH)
+    } else if !normalized.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        "may only contain letters, digits and underscores".to_string()
+    } else if RESERVED_HANDLES.contains(&normalized.as_str()) {
+        "is reserved".to_string()
+    } else {
+        return Ok(normalized);
+    };
+
+    Err(ContractError::InvalidHandle { handle: handle.to_string(), reason })
+}
+
+// Handles resolving to an Account
+fn account_handles(storage: &dyn Storage, account: &str) -> StdResult<Vec<(String, Handle)>> {
+    handles()
+        .idx
+        .account
+        .prefix(account.to_string())
+        .range(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+fn move_handles(storage: &mut dyn Storage, account: &str, new_owner: &Addr) -> StdResult<()> {
+    for (handle, mut entry) in account_handles(storage, account)? {
+        entry.owner = new_owner.clone();
+        handles().save(storage, &handle, &entry)?;
+    }
+    Ok(())
+}
+
+fn release_handles(storage: &mut dyn Storage, account: &str) -> StdResult<()> {
+    for (handle, _) in account_handles(storage, account)? {
+        handles().remove(storage, &handle)?;
+    }
+    Ok(())
+}
+
+// First live cyberlink typed by, linking from or to a fid, deleted ones can't be restored while an endpoint is gone
+fn live_reference(storage: &dyn Storage, fid: &str) -> StdResult<Option<String>> {
+    let indexes = cyberlinks().idx;
+    for index in [&indexes.type_, &indexes.from, &indexes.to] {
+        for item in index.prefix(fid.to_string()).range(storage, None, None, Order::Ascending) {
+            let (gid, state) = item?;
+            if !DELETED_GIDS.has(storage, gid) {
+                return Ok(Some(state.fid.unwrap_or_else(|| gid.to_string())));
+            }
+        }
+    }
+    Ok(None)
+}
+
+// --- Ownership Helper Functions ---
+
+// Access list of a node, empty when the owner didn't grant access to anyone
+fn node_acl(storage: &dyn Storage, gid: u64) -> StdResult<NodeAcl> {
+    Ok(NODE_ACL.may_load(storage, gid)?.unwrap_or_default())
+}
+
+// Transfers a cyberlink of the sender right away or leaves it pending until the recipient accepts
+fn transfer_or_propose(
+    storage: &mut dyn Storage,
+    sender: &Addr,
+    fid: &str,
+    new_owner: &Addr,
+    require_accept: bool,
+    time: Timestamp,
+) -> Result<u64, ContractError> {
+    let gid = NAMED_CYBERLINKS.may_load(storage, fid)?.ok_or_else(|| ContractError::NotFound { fid: fid.to_string() })?;
+    if DELETED_GIDS.has(storage, gid) {
+        return Err(ContractError::DeletedCyberlink { fid: fid.to_string() });
+    }
+
+    let cyberlink_state = cyberlinks().load(storage, gid)?;
+    if cyberlink_state.owner != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if require_accept {
+        PENDING_TRANSFERS.save(storage, gid, new_owner)?;
+    } else {
+        transfer_cyberlink(storage, gid, cyberlink_state, new_owner, time)?;
+    }
+    Ok(gid)
+}
+
+// Moves a cyberlink to a new owner, the owner indexes follow the state and the counters are moved
+fn transfer_cyberlink(
+    storage: &mut dyn Storage,
+    gid: u64,
+    old_state: CyberlinkState,
+    new_owner: &Addr,
+    time: Timestamp,
+) -> Result<(), ContractError> {
+    let new_state = CyberlinkState {
+        owner: new_owner.clone(),
+        ..old_state.clone()
+    };
+    // The reaction is moved to the new owner, who may not have one on the target yet
+    remove_reaction(storage, &old_state)?;
+    add_reaction(storage, gid, &new_state)?;
+
+    cyberlinks().replace(storage, gid, Some(&new_state), Some(&old_state))?;
+    PENDING_TRANSFERS.remove(storage, gid);
+    // Access granted by the previous owner doesn't carry over
+    NODE_ACL.remove(storage, gid);
+    // Handles of an Account follow it to the new owner
+    if let Some(fid) = &old_state.fid {
+        move_handles(storage, fid, new_owner)?;
+    }
+    record_change(storage, gid, ChangeOp::Transfer, time)?;
+
+    decrement_owner_stats(storage, &old_state.owner, &old_state.type_)?;
+    increment_owner_stats(storage, new_owner, &old_state.type_)?;
+
+    Ok(())
+}
+
+// --- Field Index Helper Functions ---
+
+// Values of the indexed fields of a cyberlink, scalars are indexed by their string or JSON text
+fn indexed_values(storage: &dyn Storage, cyberlink: &CyberlinkState) -> StdResult<Vec<(String, String)>> {
+    let fields = type_rules(storage, &cyberlink.type_)?.indexed_fields;
+    if fields.is_empty() {
+        return Ok(vec![]);
+    }
+    let Ok(serde_json::Value::Object(value)) = serde_json::from_str(&cyberlink.value) else {
+        return Ok(vec![]);
+    };
+    Ok(fields
+        .into_iter()
+        .filter_map(|field| {
+            let text = match value.get(&field)? {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Number(n) => n.to_string(),
+                serde_json::Value::Bool(b) => b.to_string(),
+                _ => return None,
+            };
+            Some((field, text))
+        })
+        .collect())
+}
+
+fn index_fields(storage: &mut dyn Storage, gid: u64, cyberlink: &CyberlinkState) -> StdResult<()> {
+    for (field, value) in indexed_values(storage, cyberlink)? {
+        FIELD_INDEX.save(storage, ((&cyberlink.type_, &field), &value, gid), &true)?;
+    }
+    Ok(())
+}
+
+fn unindex_fields(storage: &mut dyn Storage, gid: u64, cyberlink: &CyberlinkState) -> StdResult<()> {
+    for (field, value) in indexed_values(storage, cyberlink)? {
+        FIELD_INDEX.remove(storage, ((&cyberlink.type_, &field), &value, gid));
+    }
+    Ok(())
+}
+
+// --- Reaction Helper Functions ---
+
+fn is_reaction(cyberlink: &CyberlinkState) -> bool {
+    base_type_name(&cyberlink.type_) == "ReactsTo"
+}
+
+// Counts a live reaction in the tally of its target, rejecting a second one by the same owner
+fn add_reaction(storage: &mut dyn Storage, gid: u64, cyberlink: &CyberlinkState) -> Result<(), ContractError> {
+    if !is_reaction(cyberlink) {
+        return Ok(());
+    }
+    if OWNER_REACTIONS.has(storage, (&cyberlink.to, &cyberlink.owner)) {
+        return Err(ContractError::AlreadyReacted { target: cyberlink.to.clone() });
+    }
+    OWNER_REACTIONS.save(storage, (&cyberlink.to, &cyberlink.owner), &gid)?;
+    adjust_tally(storage, &cyberlink.to, &cyberlink.value, true)?;
+    Ok(())
+}
+
+fn remove_reaction(storage: &mut dyn Storage, cyberlink: &CyberlinkState) -> StdResult<()> {
+    if !is_reaction(cyberlink) {
+        return Ok(());
+    }
+    OWNER_REACTIONS.remove(storage, (&cyberlink.to, &cyberlink.owner));
+    adjust_tally(storage, &cyberlink.to, &cyberlink.value, false)
+}
+
+fn adjust_tally(storage: &mut dyn Storage, target: &str, value: &str, increment: bool) -> StdResult<()> {
+    let count = REACTION_TALLIES.may_load(storage, (target, value))?.unwrap_or(0);
+    let count = if increment { count + 1 } else { count.saturating_sub(1) };
+    if count == 0 {
+        REACTION_TALLIES.remove(storage, (target, value));
+    } else {
+        REACTION_TALLIES.save(storage, (target, value), &count)?;
+    }
+    Ok(())
+}
+
+// --- Ranking Helper Functions ---
+
+// Runs the current phase for at most `budget` steps, moving to the next phase when it is done
+fn rank_step(storage: &mut dyn Storage, run: &mut RankRun, budget: usize) -> StdResult<usize> {
+    match run.phase {
+        RankPhase::Clear => {
+            let mut used = clear_keys(storage, &RANK_NODES, budget)?;
+            used += clear_keys(storage, &RANK_CURRENT, budget - used)?;
+            used += clear_keys(storage, &RANK_NEXT, budget - used)?;
+            if used < budget {
+                run.phase = RankPhase::Collect;
+            }
+            Ok(used)
+        }
+        RankPhase::Collect => {
+            let (links, done) = next_rank_links(storage, run, budget)?;
+            for (gid, link) in &links {
+                if !is_rank_link(storage, run, *gid, link)
+                    || !is_live_node(storage, &link.from)?
+                    || !is_live_node(storage, &link.to)? {
+                    continue;
+                }
+                add_rank_node(storage, run, &link.from, true)?;
+                add_rank_node(storage, run, &link.to, false)?;
+            }
+            if done {
+                // Every node starts with an equal score
+                if run.node_count > 0 {
+                    run.dangling = Decimal::from_ratio(run.node_count - run.linked_count, run.node_count);
+                }
+                run.phase = if run.node_count > 0 { RankPhase::Distribute } else { RankPhase::Publish };
+            }
+            Ok(links.len())
+        }
+        RankPhase::Distribute => {
+            let (links, done) = next_rank_links(storage, run, budget)?;
+            for (gid, link) in &links {
+                if !is_rank_link(storage, run, *gid, link) {
+                    continue;
+                }
+                // Only links between collected nodes, links changed during the run may be partly reflected
+                let Some(from) = RANK_NODES.may_load(storage, &link.from)? else { continue };
+                if from.out_degree == 0 || !RANK_NODES.has(storage, &link.to) {
+                    continue;
+                }
+                let share = current_rank(storage, run, &link.from)? / Decimal::from_ratio(from.out_degree, 1u64);
+                let next = RANK_NEXT.may_load(storage, &link.to)?.unwrap_or_default() + share;
+                RANK_NEXT.save(storage, &link.to, &next)?;
+            }
+            if done {
+                run.phase = RankPhase::Apply;
+                run.next_dangling = Decimal::zero();
+            }
+            Ok(links.len())
+        }
+        RankPhase::Apply => {
+            let (nodes, done) = next_rank_nodes(storage, run, budget)?;
+            let node_count = Decimal::from_ratio(run.node_count, 1u64);
+            let base = (Decimal::one() - run.damping) / node_count;
+            for (fid, node) in &nodes {
+                let incoming = RANK_NEXT.may_load(storage, fid)?.unwrap_or_default();
+                RANK_NEXT.remove(storage, fid);
+                let rank = base + run.damping * (incoming + run.dangling / node_count);
+                RANK_CURRENT.save(storage, fid, &rank)?;
+                if node.out_degree == 0 {
+                    run.next_dangling += rank;
+                }
+            }
+            if done {
+                run.iteration += 1;
+                run.dangling = run.next_dangling;
+                run.phase = if run.iteration < run.iterations { RankPhase::Distribute } else { RankPhase::Publish };
+            }
+            Ok(nodes.len())
+        }
+        RankPhase::Publish => {
+            let (nodes, done) = next_rank_nodes(storage, run, budget)?;
+            for (fid, node) in &nodes {
+                let score = RankScore { type_: node.type_.clone(), score: current_rank(storage, run, fid)? };
+                rank_scores().save(storage, fid, &score)?;
+            }
+            if done {
+                run.phase = RankPhase::Prune;
+            }
+            Ok(nodes.len())
+        }
+        RankPhase::Prune => {
+            let start = run.node_cursor.as_deref().map(Bound::exclusive);
+            let fids = rank_scores()
+                .keys(storage, start, None, Order::Ascending)
+                .take(budget)
+                .collect::<StdResult<Vec<String>>>()?;
+            for fid in &fids {
+                if !RANK_NODES.has(storage, fid) {
+                    rank_scores().remove(storage, fid)?;
+                }
+            }
+            run.node_cursor = fids.last().cloned();
+            if fids.len() < budget {
+                run.phase = RankPhase::Done;
+                run.node_cursor = None;
+            }
+            Ok(fids.len())
+        }
+        RankPhase::Done => Ok(0),
+    }
+}
+
+// Removes up to `budget` entries of a working map
+fn clear_keys<T: Serialize + DeserializeOwned>(storage: &mut dyn Storage, map: &Map<&str, T>, budget: usize) -> StdResult<usize> {
+    let keys = map
+        .keys(storage, None, None, Order::Ascending)
+        .take(budget)
+        .collect::<StdResult<Vec<String>>>()?;
+    for key in &keys {
+        map.remove(storage, key);
+    }
+    Ok(keys.len())
+}
+
+// Next links of the selected types after the cursor, flagging when all of them were read
+fn next_rank_links(storage: &dyn Storage, run: &mut RankRun, budget: usize) -> StdResult<(Vec<(u64, CyberlinkState)>, bool)> {
+    let mut links = vec![];
+    while links.len() < budget {
+        let Some(type_) = run.link_types.get(run.type_index as usize) else {
+            run.type_index = 0;
+            return Ok((links, true));
+        };
+        let wanted = budget - links.len();
+        let batch = cyberlinks()
+            .idx
+            .type_
+            .prefix(type_.clone())
+            .range(storage, run.link_cursor.map(Bound::exclusive), None, Order::Ascending)
+            .take(wanted)
+            .collect::<StdResult<Vec<_>>>()?;
+        if batch.len() < wanted {
+            run.type_index += 1;
+            run.link_cursor = None;
+        } else {
+            run.link_cursor = batch.last().map(|(gid, _)| *gid);
+        }
+        links.extend(batch);
+    }
+    Ok((links, false))
+}
+
+// Next collected nodes after the cursor, flagging when all of them were read
+fn next_rank_nodes(storage: &dyn Storage, run: &mut RankRun, budget: usize) -> StdResult<(Vec<(String, RankNode)>, bool)> {
+    let start = run.node_cursor.as_deref().map(Bound::exclusive);
+    let nodes = RANK_NODES
+        .range(storage, start, None, Order::Ascending)
+        .take(budget)
+        .collect::<StdResult<Vec<_>>>()?;
+    let done = nodes.len() < budget;
+    run.node_cursor = if done { None } else { nodes.last().map(|(fid, _)| fid.clone()) };
+    Ok((nodes, done))
+}
+
+// Live links of the run between two nodes, "Any" endpoints aren't nodes
+fn is_rank_link(storage: &dyn Storage, run: &RankRun, gid: u64, link: &CyberlinkState) -> bool {
+    gid <= run.max_gid
+        && link.from != "Any"
+        && link.to != "Any"
+        && !DELETED_GIDS.has(storage, gid)
+}
+
+fn is_live_node(storage: &dyn Storage, fid: &str) -> StdResult<bool> {
+    Ok(match NAMED_CYBERLINKS.may_load(storage, fid)? {
+        Some(gid) => !DELETED_GIDS.has(storage, gid),
+        None => false,
+    })
+}
+
+fn add_rank_node(storage: &mut dyn Storage, run: &mut RankRun, fid: &str, outgoing: bool) -> StdResult<()> {
+    let mut node = match RANK_NODES.may_load(storage, fid)? {
+        Some(node) => node,
+        None => {
+            run.node_count += 1;
+            let gid = NAMED_CYBERLINKS.load(storage, fid)?;
+            RankNode { type_: cyberlinks().load(storage, gid)?.type_, out_degree: 0 }
+        }
+    };
+    if outgoing {
+        if node.out_degree == 0 {
+            run.linked_count += 1;
+        }
+        node.out_degree += 1;
+    }
+    RANK_NODES.save(storage, fid, &node)
+}
+
+// Score of a node in the current iteration, equal for all nodes before the first one
+fn current_rank(storage: &dyn Storage, run: &RankRun, fid: &str) -> StdResult<Decimal> {
+    if run.iteration == 0 {
+        return Ok(Decimal::from_ratio(1u64, run.node_count));
+    }
+    Ok(RANK_CURRENT.may_load(storage, fid)?.unwrap_or_default())
+}
+
+// --- Change Log Helper Functions ---
+
+// Appends an entry to the global change log under the next sequence number
+pub(crate) fn record_change(
+    storage: &mut dyn Storage,
+    gid: u64,
+    op: ChangeOp,
+    time: Timestamp,
+) -> StdResult<u64> {
+    let seq = CHANGE_SEQ.may_load(storage)?.unwrap_or(0) + 1;
+    CHANGE_SEQ.save(storage, &seq)?;
+    CHANGES.save(storage, seq, &Change { gid, op, time })?;
+    Ok(seq)
+}
+
+// --- Type Registry Helper Functions ---
+
+// Registers a type definition, keeping the entry of a type that is already registered
+pub(crate) fn register_type(
+    storage: &mut dyn Storage,
+    name: &str,
+    meta: TypeMeta,
+) -> StdResult<()> {
+    if !TYPES.has(storage, name) {
+        TYPES.save(storage, name, &meta)?;
+    }
+    Ok(())
+}
+
+// Rules of a type, falling back to permissive rules for types outside of the registry
+fn type_rules(storage: &dyn Storage, type_: &str) -> StdResult<TypeMeta> {
+    Ok(TYPES.may_load(storage, type_)?.unwrap_or_else(TypeMeta::unregistered))
+}
+
+// Rejects retired types and returns a warning attribute for deprecated ones
+fn check_lifecycle(rules: &TypeMeta, type_: &str) -> Result<Option<Attribute>, ContractError> {
+    match rules.lifecycle {
+        TypeLifecycle::Active => Ok(None),
+        TypeLifecycle::Deprecated => Ok(Some(Attribute::new("warning", format!("deprecated type: {}", type_)))),
+        TypeLifecycle::Retired => Err(ContractError::TypeRetired { type_: type_.to_string() }),
+    }
+}
+
+// Checks that from/to match the kind of the type and that self-loops are allowed
+fn check_shape(
+    rules: &TypeMeta,
+    type_: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<(), ContractError> {
+    let shape_allowed = match (from, to) {
+        (None, None) => rules.kind != TypeKind::Edge,
+        (Some(_), Some(_)) => rules.kind != TypeKind::Node,
+        _ => false,
+    };
+    if !shape_allowed {
+        return Err(ContractError::InvalidCyberlink {
+            from: from.unwrap_or("None").to_string(),
+            to: to.unwrap_or("None").to_string(),
+            type_: type_.to_string(),
+        });
+    }
+
+    if let (Some(from), Some(to)) = (from, to) {
+        if from == to && !rules.allow_self_loop {
+            return Err(ContractError::SelfLoopNotAllowed {
+                type_: type_.to_string(),
+                fid: from.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// --- Counter Helper Functions ---
+
+fn increment_stats(
+    storage: &mut dyn Storage,
     owner: &Addr,
     type_: &str,
 ) -> StdResult<()> {
-    // Decrement owner count, removing if zero
-    let owner_count = OWNER_LINK_COUNT.load(storage, owner)?;
-    if owner_count <= 1 {
-        OWNER_LINK_COUNT.remove(storage, owner);
-    } else {
-        OWNER_LINK_COUNT.save(storage, owner, &(owner_count - 1))?;
-    }
+    // Increment type count
+    let type_count = TYPE_LINK_COUNT.may_load(storage, type_)?.unwrap_or(0) + 1;
+    TYPE_LINK_COUNT.save(storage, type_, &type_count)?;
+
+    increment_owner_stats(storage, owner, type_)
+}
+
+fn increment_owner_stats(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    type_: &str,
+) -> StdResult<()> {
+    // Increment owner count
+    let owner_count = OWNER_LINK_COUNT.may_load(storage, owner)?.unwrap_or(0) + 1;
+    OWNER_LINK_COUNT.save(storage, owner, &owner_count)?;
+
+    // Increment owner-type count
+    let owner_type_count = OWNER_TYPE_LINK_COUNT.may_load(storage, (owner, type_))?.unwrap_or(0) + 1;
+    OWNER_TYPE_LINK_COUNT.save(storage, (owner, type_), &owner_type_count)?;
 
+    Ok(())
+}
+
+fn decrement_stats(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    type_: &str,
+) -> StdResult<()> {
     // Decrement type count, removing if zero
     let type_count = TYPE_LINK_COUNT.load(storage, type_)?;
     if type_count <= 1 {
@@ -388,6 +1588,22 @@ fn decrement_stats(
         TYPE_LINK_COUNT.save(storage, type_, &(type_count - 1))?;
     }
 
+    decrement_owner_stats(storage, owner, type_)
+}
+
+fn decrement_owner_stats(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    type_: &str,
+) -> StdResult<()> {
+    // Decrement owner count, removing if zero
+    let owner_count = OWNER_LINK_COUNT.load(storage, owner)?;
+    if owner_count <= 1 {
+        OWNER_LINK_COUNT.remove(storage, owner);
+    } else {
+        OWNER_LINK_COUNT.save(storage, owner, &(owner_count - 1))?;
+    }
+
     // Decrement owner-type count, removing if zero
     let owner_type_count = OWNER_TYPE_LINK_COUNT.load(storage, (owner, type_))?;
     if owner_type_count <= 1 {
@@ -399,6 +1615,58 @@ fn decrement_stats(
     Ok(())
 }
 
+// Degree counters of the nodes a cyberlink connects, "Any" endpoints aren't nodes
+fn increment_degrees(storage: &mut dyn Storage, cyberlink: &CyberlinkState) -> StdResult<()> {
+    if cyberlink.from != "Any" {
+        adjust_degree(storage, &OUT_DEGREE, &OUT_DEGREE_BY_TYPE, &cyberlink.from, &cyberlink.type_, true)?;
+    }
+    if cyberlink.to != "Any" {
+        adjust_degree(storage, &IN_DEGREE, &IN_DEGREE_BY_TYPE, &cyberlink.to, &cyberlink.type_, true)?;
+    }
+    Ok(())
+}
+
+fn decrement_degrees(storage: &mut dyn Storage, cyberlink: &CyberlinkState) -> StdResult<()> {
+    if cyberlink.from != "Any" {
+        adjust_degree(storage, &OUT_DEGREE, &OUT_DEGREE_BY_TYPE, &cyberlink.from, &cyberlink.type_, false)?;
+    }
+    if cyberlink.to != "Any" {
+        adjust_degree(storage, &IN_DEGREE, &IN_DEGREE_BY_TYPE, &cyberlink.to, &cyberlink.type_, false)?;
+    }
+    Ok(())
+}
+
+// Adjusts a node's total and per-type degree by one, removing counters that reach zero
+fn adjust_degree(
+    storage: &mut dyn Storage,
+    total: &Map<&str, u64>,
+    by_type: &Map<(&str, &str), u64>,
+    fid: &str,
+    type_: &str,
+    increment: bool,
+) -> StdResult<()> {
+    let adjust = |count: Option<u64>| {
+        let count = count.unwrap_or(0);
+        if increment { count + 1 } else { count.saturating_sub(1) }
+    };
+
+    let count = adjust(total.may_load(storage, fid)?);
+    if count == 0 {
+        total.remove(storage, fid);
+    } else {
+        total.save(storage, fid, &count)?;
+    }
+
+    let type_count = adjust(by_type.may_load(storage, (fid, type_))?);
+    if type_count == 0 {
+        by_type.remove(storage, (fid, type_));
+    } else {
+        by_type.save(storage, (fid, type_), &type_count)?;
+    }
+
+    Ok(())
+}
+
 fn validate_type_compatibility_for_cyberlink2(
     link_type_state: &CyberlinkState,
     node_type: &str, // Type of the node being created
@@ -468,6 +1736,7 @@ fn validate_type_compatibility_for_cyberlink2(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_create_cyberlink2(
     mut deps: DepsMut,
     env: Env,
@@ -488,13 +1757,19 @@ pub fn execute_create_cyberlink2(
         };
 
     // Type Existence
-    let _node_type_gid = NAMED_CYBERLINKS.may_load(deps.storage, &node_type)? // Renamed to avoid shadowing
+    let node_type_gid = NAMED_CYBERLINKS.may_load(deps.storage, &node_type)?
         .ok_or_else(|| ContractError::TypeNotExists { type_: node_type.clone() })?;
+    if DELETED_GIDS.has(deps.storage, node_type_gid) {
+        return Err(ContractError::DeletedCyberlink { fid: node_type.clone() });
+    }
     // Load node type state only if needed for strict validation later, otherwise existence check is enough
     // let _node_type_state = cyberlinks().load(deps.storage, node_type_gid)?;
     
     let link_type_gid = NAMED_CYBERLINKS.may_load(deps.storage, &link_type)?
         .ok_or_else(|| ContractError::TypeNotExists { type_: link_type.clone() })?;
+    if DELETED_GIDS.has(deps.storage, link_type_gid) {
+        return Err(ContractError::DeletedCyberlink { fid: link_type.clone() });
+    }
     let link_type_state = cyberlinks().load(deps.storage, link_type_gid)?;
 
     // Existing Node Validation
@@ -512,6 +1787,22 @@ pub fn execute_create_cyberlink2(
     }
     let existing_node_state = cyberlinks().load(deps.storage, existing_node_gid)?;
 
+    // Shape and Lifecycle Validation: the new cyberlink must be a node, the link must be an edge
+    let node_rules = type_rules(deps.storage, &node_type)?;
+    let link_rules = type_rules(deps.storage, &link_type)?;
+    let warnings: Vec<Attribute> = check_lifecycle(&node_rules, &node_type)?
+        .into_iter()
+        .chain(check_lifecycle(&link_rules, &link_type)?)
+        .collect();
+    check_shape(&node_rules, &node_type, None, None)?;
+    let new_node_ref = format!("new:{}", node_type);
+    let (shape_from, shape_to) = if link_from_new {
+        (new_node_ref.as_str(), existing_node_fid.as_str())
+    } else {
+        (existing_node_fid.as_str(), new_node_ref.as_str())
+    };
+    check_shape(&link_rules, &link_type, Some(shape_from), Some(shape_to))?;
+
     // Type Compatibility Validation (Using loaded states)
     validate_type_compatibility_for_cyberlink2(
         &link_type_state,
@@ -557,6 +1848,7 @@ pub fn execute_create_cyberlink2(
         .add_attribute("node_fid", node_fid)
         .add_attribute("link_gid", link_gid.to_string())
         .add_attribute("link_fid", link_fid)
+        .add_attributes(warnings)
     )
 }
 
@@ -1,16 +1,20 @@
 use cosmwasm_std::Uint64;
 use cosmwasm_schema::{cw_serde, QueryResponses};
 #[allow(unused_imports)]
-use crate::query::{ConfigResponse, StateResponse};
+use crate::query::{AclResponse, ChangeResponse, ConfigResponse, DeletedResponse, Page, HandleResponse, MutualConnectionsResponse, PendingTransferResponse, RankResponse, SubgraphResponse, SuggestConnectionsResponse, StateResponse, TypeInfoResponse, TypeResponse};
 #[allow(unused_imports)]
-use crate::state::CyberlinkState;
-use cosmwasm_std::Timestamp;
+use crate::state::{CyberlinkState, HandleFee, TypeKind, TypeLifecycle};
+use cosmwasm_std::{Binary, Decimal, Order, SignedDecimal, Timestamp};
+use std::collections::BTreeMap;
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admins: Vec<String>,
     pub executers: Vec<String>,
     pub semantic_cores: Vec<String>,
+    // Cores loaded under their own name as namespace, e.g. `lens.Account`
+    #[serde(default)]
+    pub namespaced_semantic_cores: Vec<String>,
 }
 
 #[cw_serde]
@@ -31,31 +35,181 @@ pub struct Cyberlink {
     pub value: Option<String>
 }
 
+// A (from, type, to) pattern over cyberlinks, terms starting with `?` are variables, e.g. ("?a", "Follows", "?b")
+#[cw_serde]
+pub struct TriplePattern {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+}
+
+// A node or edge in the shape of the semantic core JSON files, "Any" endpoints are left out
+#[cw_serde]
+pub struct SemcoreEntry {
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    // JSON objects and arrays are inlined, any other value is kept as a string
+    pub value: Option<serde_json::Value>,
+}
+
+impl SemcoreEntry {
+    pub fn from_state(state: &CyberlinkState) -> Self {
+        let endpoint = |fid: &str| (fid != "Any").then(|| fid.to_string());
+        let value = match serde_json::from_str::<serde_json::Value>(&state.value) {
+            _ if state.value.is_empty() => None,
+            Ok(json) if json.is_object() || json.is_array() => Some(json),
+            _ => Some(serde_json::Value::String(state.value.clone())),
+        };
+        SemcoreEntry {
+            id: state.fid.clone(),
+            type_: state.type_.clone(),
+            from: endpoint(&state.from),
+            to: endpoint(&state.to),
+            value,
+        }
+    }
+
+    // Value as stored in a cyberlink, the inverse of from_state
+    pub fn value_string(&self) -> Option<String> {
+        self.value.as_ref().map(|value| match value {
+            serde_json::Value::String(s) => s.clone(),
+            json => json.to_string(),
+        })
+    }
+}
+
+// Condition on the JSON document in the value of a cyberlink, at a JSON pointer such as /status
+#[cw_serde]
+pub struct ValueFilter {
+    pub pointer: String,
+    pub condition: ValueCondition,
+}
+
+#[cw_serde]
+pub enum ValueCondition {
+    // Strings compare as they are, other values by their JSON text, e.g. true or 42
+    Equals(String),
+    // Strings only
+    Prefix(String),
+    // Numbers only
+    Gt(SignedDecimal),
+    Gte(SignedDecimal),
+    Lt(SignedDecimal),
+    Lte(SignedDecimal),
+}
+
 #[cw_serde]
 pub enum ExecuteMsg {
     CreateNamedCyberlink {
         name: String,
         cyberlink: Cyberlink,
     },
+    /// Creates a cyberlink named `<sender>/<name>`, reserved for the sender
+    CreateOwnedNamedCyberlink {
+        name: String,
+        cyberlink: Cyberlink,
+    },
+    /// Transfers a cyberlink of the sender, with require_accept the recipient has to accept it.
+    /// Owner-namespaced names keep their name, so that references to them stay valid
+    TransferOwnership {
+        fid: String,
+        new_owner: String,
+        require_accept: Option<bool>,
+    },
+    TransferOwnerships {
+        fids: Vec<String>,
+        new_owner: String,
+        require_accept: Option<bool>,
+    },
+    AcceptOwnership {
+        fid: String,
+    },
+    CancelOwnershipTransfer {
+        fid: String,
+    },
+    /// Replaces the editors and deleters of a cyberlink of the sender
+    SetAcl {
+        fid: String,
+        editors: Vec<String>,
+        deleters: Vec<String>,
+    },
     CreateCyberlink {
         cyberlink: Cyberlink,
     },
     CreateCyberlinks {
         cyberlinks: Vec<Cyberlink>,
     },
+    /// Creates exported entries under new FIDs, references to entries imported earlier under the
+    /// same import name are remapped, so a large subgraph can be sent in several transactions.
+    /// Every entry needs an id, Type entries keep it as their name
+    ImportSubgraph {
+        import: String,
+        entries: Vec<SemcoreEntry>,
+    },
     UpdateCyberlink {
         fid: String,
         value: Option<String>,
     },
+    /// Marks a cyberlink as deleted, the state is kept until it is purged
     DeleteCyberlink {
         fid: String,
     },
+    RestoreCyberlink {
+        fid: String,
+    },
+    /// Removes a deleted cyberlink from storage and frees its FID, refused while live links reference it
+    PurgeCyberlink {
+        fid: String,
+    },
     UpdateAdmins {
         new_admins: Vec<String>
     },
     UpdateExecutors {
         new_executors: Vec<String>
     },
+    UpdateTypeRules {
+        #[serde(rename = "type")]
+        type_: String,
+        kind: Option<TypeKind>,
+        allow_self_loop: Option<bool>,
+        // Can only change while the type has no cyberlinks, existing ones are not reindexed
+        indexed_fields: Option<Vec<String>>,
+    },
+    SetTypeLifecycle {
+        #[serde(rename = "type")]
+        type_: String,
+        lifecycle: TypeLifecycle,
+    },
+    /// Sets the fee for claiming handles, None makes claiming free
+    UpdateHandleFee {
+        fee: Option<HandleFee>,
+    },
+    /// Claims a handle for an Account of the sender or points an owned handle to another Account
+    ClaimHandle {
+        handle: String,
+        account: String,
+    },
+    ReleaseHandle {
+        handle: String,
+    },
+    TransferHandle {
+        handle: String,
+        new_owner: String,
+    },
+    /// Starts a ranking run over links of the given types, abandoning a run in progress (admin only)
+    StartRanking {
+        link_types: Vec<String>,
+        // Defaults to 0.85
+        damping: Option<Decimal>,
+        iterations: Option<u32>,
+    },
+    /// Advances the ranking run by at most limit links or nodes (admin only)
+    ContinueRanking {
+        limit: Option<u32>,
+    },
     CreateCyberlink2 {
         /// Data for the new node to be created.
         node_type: String,
@@ -82,21 +236,54 @@ pub enum QueryMsg {
         #[serde(rename = "type")]
         type_: Option<String>,
     },
+    /// Incoming and outgoing link counts of a node, in total and per link type
+    #[returns(NodeStatsResponse)]
+    NodeStats { fid: String },
+    /// Reaction counts on a target, per value of the ReactsTo links (the reaction kind, e.g. "👍")
+    #[returns(ReactionsResponse)]
+    Reactions { target_fid: String },
     #[returns(ConfigResponse)]
     Config {},
     #[returns(StateResponse)]
     DebugState {},
     
+    /// Global change log in sequence order, for incremental sync
+    #[returns(Page<ChangeResponse>)]
+    Changes {
+        // Changes after the given seq, e.g. the last one already synced
+        after_seq: Option<u64>,
+        // next_cursor of the previous page, the JSON-encoded seq of its last change, takes
+        // precedence over after_seq
+        start_after: Option<Binary>,
+        // Ascending by default, like the other sync feed DeletedSince
+        order: Option<Order>,
+        limit: Option<u32>,
+    },
+
     // Global IDs API
+    // List queries return Order::Descending (newest first) by default, except the Changes and DeletedSince
+    // sync feeds, pass next_cursor as start_after to continue
     #[returns(Uint64)]
     LastGID {},
     #[returns(CyberlinkState)]
     CyberlinkByGID {
         gid: Uint64,
     },
-    #[returns(Vec<(u64, CyberlinkState)>)]
+    #[returns(Page<(u64, CyberlinkState)>)]
     CyberlinksByGIDs {
-        start_after_gid: Option<u64>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
+        limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
+    },
+    /// Deletions after the given time, ordered by deletion time, for mirroring the graph
+    #[returns(Page<DeletedResponse>)]
+    DeletedSince {
+        time: Timestamp,
+        start_after: Option<Binary>,
+        // Ascending by default, oldest deletion first
+        order: Option<Order>,
         limit: Option<u32>,
     },
     #[returns(Vec<(u64, CyberlinkState)>)]
@@ -109,66 +296,226 @@ pub enum QueryMsg {
     CyberlinkByFID {
         fid: String,
     },
-    #[returns(Vec<(String, CyberlinkState)>)]
+    #[returns(Page<(String, CyberlinkState)>)]
     CyberlinksByFIDs {
-        start_after_fid: Option<String>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
         limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
+    },
+    /// Cyberlinks whose FID or name starts with the prefix, e.g. "Post:" or "lens."
+    #[returns(Page<(String, CyberlinkState)>)]
+    CyberlinksByFIDPrefix {
+        prefix: String,
+        start_after: Option<Binary>,
+        order: Option<Order>,
+        limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
     },
     #[returns(Vec<(String, CyberlinkState)>)]
     CyberlinksSetByFIDs {
         fids: Vec<String>,
     },
+    #[returns(AclResponse)]
+    Acl {
+        fid: String,
+    },
+    #[returns(PendingTransferResponse)]
+    PendingTransfer {
+        fid: String,
+    },
 
     // Formatted IDs API (WIP)
-    #[returns(Vec<(u64, CyberlinkState)>)]
+    #[returns(Page<(u64, CyberlinkState)>)]
     CyberlinksByType {
         #[serde(rename = "type")]
         type_: String,
-        start_after_gid: Option<u64>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
         limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
+        // Only return cyberlinks whose value matches, reading a bounded number of them per page
+        filter: Option<ValueFilter>,
     },
-    #[returns(Vec<(u64, CyberlinkState)>)]
+    #[returns(Page<(u64, CyberlinkState)>)]
     CyberlinksByFrom {
         from: String,
-        start_after_gid: Option<u64>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
         limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
+        // Only return cyberlinks whose value matches, reading a bounded number of them per page
+        filter: Option<ValueFilter>,
     },
-    #[returns(Vec<(u64, CyberlinkState)>)]
+    /// Posts and Reposts links of the accounts an account Follows, newest first. Only the most
+    /// recently followed accounts are merged: 400 author and link type streams in total, i.e. the
+    /// last 200 follows with the social core, fewer when namespaced cores add Posts or Reposts types.
+    /// There is no order parameter, the streams are merged newest first as a timeline is read
+    #[returns(Page<(u64, CyberlinkState)>)]
+    Feed {
+        account_fid: String,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+    /// Second-degree neighbors of a node over a link type, ranked by shared first-degree neighbors
+    #[returns(SuggestConnectionsResponse)]
+    SuggestConnections {
+        fid: String,
+        link_type: String,
+        limit: Option<u32>,
+    },
+    /// Nodes both a and b link to with a link type
+    #[returns(MutualConnectionsResponse)]
+    MutualConnections {
+        a: String,
+        b: String,
+        link_type: String,
+    },
+    #[returns(Page<(u64, CyberlinkState)>)]
     CyberlinksByTo {
         to: String,
-        start_after_gid: Option<u64>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
         limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
+        // Only return cyberlinks whose value matches, reading a bounded number of them per page
+        filter: Option<ValueFilter>,
     },
-    #[returns(Vec<(u64, CyberlinkState)>)]
+    #[returns(Page<(u64, CyberlinkState)>)]
     CyberlinksByOwner {
         owner: String,
-        start_after_gid: Option<u64>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
         limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
     },
-    #[returns(Vec<(u64, CyberlinkState)>)]
+    #[returns(Page<(u64, CyberlinkState)>)]
     CyberlinksByOwnerTime {
         owner: String,
         start_time: Timestamp,
         end_time: Option<Timestamp>,
-        start_after_gid: Option<u64>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
         limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
     },
-    #[returns(Vec<(u64, CyberlinkState)>)]
+    #[returns(Page<(u64, CyberlinkState)>)]
     CyberlinksByOwnerTimeAny {
         owner: String,
         start_time: Timestamp,
         end_time: Option<Timestamp>,
-        start_after_gid: Option<u64>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
         limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
     },
-    #[returns(Vec<(u64, CyberlinkState)>)]
+    #[returns(Page<(u64, CyberlinkState)>)]
     CyberlinksByOwnerAndType {
         owner: String,
         #[serde(rename = "type")]
         type_: String,
-        start_after_gid: Option<u64>,
+        start_after: Option<Binary>,
+        order: Option<Order>,
+        limit: Option<u32>,
+        // Also return deleted cyberlinks, with deleted_at set
+        include_deleted: Option<bool>,
+    },
+    /// Live cyberlinks of a type whose indexed value field equals the given value, by GID
+    #[returns(Page<(u64, CyberlinkState)>)]
+    CyberlinksByField {
+        #[serde(rename = "type")]
+        type_: String,
+        field: String,
+        // Strings match as is, numbers and booleans by their JSON text
+        value: String,
+        start_after: Option<Binary>,
+        order: Option<Order>,
+        limit: Option<u32>,
+    },
+
+    /// Cyberlinks of a type across all owners within a time window
+    #[returns(Page<(u64, CyberlinkState)>)]
+    CyberlinksByTypeTime {
+        #[serde(rename = "type")]
+        type_: String,
+        start_time: Timestamp,
+        end_time: Option<Timestamp>,
+        // Time used for the window and ordering, created_at by default
+        time_field: Option<TimeField>,
+        order: Option<Order>,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+        include_deleted: Option<bool>,
+    },
+
+    /// Distinct variable bindings satisfying all patterns, joined in the given order. Every page
+    /// re-runs the whole join, the cursor only skips the bindings already returned. There is no
+    /// order parameter, rows come sorted by their bindings rather than from an index
+    #[returns(Page<BTreeMap<String, String>>)]
+    Match {
+        patterns: Vec<TriplePattern>,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+
+    /// Cyberlinks within `depth` hops of the roots as semcore entries, in creation order so that
+    /// every entry comes after the nodes it references
+    #[returns(SubgraphResponse)]
+    ExportSubgraph {
+        root_fids: Vec<String>,
+        depth: Option<u32>,
+        // Only follow links of these types, all types when not set
+        link_types: Option<Vec<String>>,
+        cursor: Option<Binary>,
+        limit: Option<u32>,
+    },
+
+    /// Score of a node from the last finished ranking run
+    #[returns(RankResponse)]
+    Rank { fid: String },
+    /// Highest scoring nodes of a type, from the last finished ranking run
+    #[returns(Page<RankResponse>)]
+    TopRanked {
+        #[serde(rename = "type")]
+        type_: String,
+        start_after: Option<Binary>,
+        // Descending by default, highest score first
+        order: Option<Order>,
         limit: Option<u32>,
     },
+
+    // Type system API
+    #[returns(Page<TypeResponse>)]
+    Types {
+        start_after: Option<Binary>,
+        order: Option<Order>,
+        limit: Option<u32>,
+    },
+    #[returns(TypeInfoResponse)]
+    TypeInfo {
+        #[serde(rename = "type")]
+        type_: String,
+    },
+
+    // Handles API
+    #[returns(HandleResponse)]
+    ResolveHandle {
+        handle: String,
+    },
+}
+
+#[cw_serde]
+pub enum TimeField {
+    Created,
+    Updated,
 }
 
 // Response struct for count queries
@@ -178,3 +525,28 @@ pub struct CountsResponse {
     pub type_count: Option<Uint64>,
     pub owner_type_count: Option<Uint64>,
 }
+
+// Degrees of a node over live cyberlinks, per-type counts are (link type, count) sorted by type
+#[cw_serde]
+pub struct NodeStatsResponse {
+    pub fid: String,
+    pub in_degree: Uint64,
+    pub out_degree: Uint64,
+    pub in_by_type: Vec<(String, Uint64)>,
+    pub out_by_type: Vec<(String, Uint64)>,
+}
+
+// Reactions to a target over live ReactsTo links, tallies are (reaction value, count) sorted by value
+#[cw_serde]
+pub struct ReactionsResponse {
+    pub target_fid: String,
+    pub total: Uint64,
+    pub tallies: Vec<(String, Uint64)>,
+}
+
+// Data of the ImportSubgraph response
+#[cw_serde]
+pub struct ImportSubgraphResponse {
+    // (Source FID, new FID) of the entries in the message, including ones imported before
+    pub mapping: Vec<(String, String)>,
+}
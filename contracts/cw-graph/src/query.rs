@@ -1,9 +1,14 @@
-use crate::state::{cyberlinks, CyberlinkState, CONFIG, DELETED_GIDS, GID, NAMED_CYBERLINKS, OWNER_LINK_COUNT, TYPE_LINK_COUNT, OWNER_TYPE_LINK_COUNT};
+use crate::state::{cyberlinks, ChangeOp, CyberlinkState, CHANGES, HandleFee, TypeKind, TypeLifecycle, CONFIG, handles, NODE_ACL, tombstones, PENDING_TRANSFERS, DELETED_GIDS, GID, NAMED_CYBERLINKS, TYPES, OWNER_LINK_COUNT, TYPE_LINK_COUNT, OWNER_TYPE_LINK_COUNT, IN_DEGREE, OUT_DEGREE, IN_DEGREE_BY_TYPE, OUT_DEGREE_BY_TYPE, REACTION_TALLIES, FIELD_INDEX, rank_scores};
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Deps, Env, Order, StdError, StdResult, Timestamp, Uint64};
-use cw_storage_plus::Bound;
+use cosmwasm_std::{from_json, to_json_binary, Binary, Decimal, Deps, Env, Order, SignedDecimal, StdError, StdResult, Storage, Timestamp, Uint128, Uint64};
+use cw_storage_plus::{Bound, Map, PrimaryKey};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
-use crate::msg::CountsResponse;
+use crate::msg::{CountsResponse, NodeStatsResponse, ReactionsResponse, SemcoreEntry, TimeField, TriplePattern, ValueCondition, ValueFilter};
+use crate::semcores::base_type_name;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use std::str::FromStr;
 
 pub fn query_last_gid(deps: Deps) -> StdResult<Uint64> {
     let last_id = GID.load(deps.storage)?;
@@ -13,7 +18,7 @@ pub fn query_last_gid(deps: Deps) -> StdResult<Uint64> {
 pub fn query_cyberlink_by_gid(deps: Deps, id: Uint64) -> StdResult<CyberlinkState> {
     // Check if the cyberlink is deleted
     if DELETED_GIDS.has(deps.storage, id.u64()) {
-        return Err(StdError::not_found("deleted cyberlink"));
+        return Err(deleted_error(deps.storage, id.u64()));
     }
 
     // Load the cyberlink state
@@ -25,7 +30,8 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let cfg = CONFIG.load(deps.storage)?;
     Ok(ConfigResponse {
         admins: cfg.admins.into_iter().map(|a| a.into()).collect(),
-        executors: cfg.executors.into_iter().map(|a| a.into()).collect()
+        executors: cfg.executors.into_iter().map(|a| a.into()).collect(),
+        handle_fee: cfg.handle_fee,
     })
 }
 
@@ -33,6 +39,26 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 pub struct ConfigResponse {
     pub admins: Vec<String>,
     pub executors: Vec<String>,
+    pub handle_fee: Option<HandleFee>,
+}
+
+pub fn query_resolve_handle(deps: Deps, handle: String) -> StdResult<HandleResponse> {
+    let handle = handle.to_lowercase();
+    let entry = handles().may_load(deps.storage, &handle)?
+        .ok_or_else(|| StdError::not_found(format!("handle {}", handle)))?;
+    Ok(HandleResponse {
+        handle,
+        owner: entry.owner.into(),
+        account: entry.account,
+    })
+}
+
+#[cw_serde]
+pub struct HandleResponse {
+    pub handle: String,
+    pub owner: String,
+    // FID of the Account
+    pub account: String,
 }
 
 pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
@@ -51,106 +77,420 @@ pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
     })
 }
 
+// Not found error that tells deleted cyberlinks apart from missing ones
+fn deleted_error(storage: &dyn Storage, gid: u64) -> StdError {
+    match tombstones().may_load(storage, gid) {
+        Ok(Some(tombstone)) => StdError::not_found(format!("deleted cyberlink {} (deleted at {})", gid, tombstone.deleted_at)),
+        _ => StdError::not_found("deleted cyberlink"),
+    }
+}
+
+// Deleted cyberlinks keep their state and index entries until purged, index queries skip them
+fn is_live(storage: &dyn Storage, item: &StdResult<(u64, CyberlinkState)>) -> bool {
+    match item {
+        Ok((gid, _)) => !DELETED_GIDS.has(storage, *gid),
+        Err(_) => true,
+    }
+}
+
+pub fn query_changes(
+    deps: Deps,
+    after_seq: Option<u64>,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+) -> StdResult<Page<ChangeResponse>> {
+    // A sync feed, oldest first by default. The cursor is the JSON-encoded seq of the last change returned
+    let order = order.unwrap_or(Order::Ascending);
+    let (min, max) = since_bounds(after_seq, decode_cursor::<u64>(start_after)?, order);
+
+    let changes = CHANGES
+        .range(deps.storage, min, max, order)
+        .map(|item| {
+            let (seq, change) = item?;
+            Ok(ChangeResponse {
+                seq,
+                gid: change.gid,
+                op: change.op,
+                time: change.time,
+            })
+        });
+    paginate(changes, limit, |c| c.seq)
+}
+
+#[cw_serde]
+pub struct ChangeResponse {
+    pub seq: u64,
+    pub gid: u64,
+    pub op: ChangeOp,
+    pub time: Timestamp,
+}
+
+pub fn query_deleted_since(
+    deps: Deps,
+    time: Timestamp,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+) -> StdResult<Page<DeletedResponse>> {
+    // A sync feed, oldest first by default. The cursor holds the (deleted_at, gid) key of the last deletion returned
+    let order = order.unwrap_or(Order::Ascending);
+    let cursor = decode_cursor::<(u64, u64)>(start_after)?;
+    let (min, max) = since_bounds(Some((time.nanos(), u64::MAX)), cursor, order);
+
+    let deleted = tombstones()
+        .idx
+        .deleted_at
+        .range(deps.storage, min, max, order)
+        .map(|item| {
+            let (gid, tombstone) = item?;
+            let fid = cyberlinks().may_load(deps.storage, gid)?.and_then(|c| c.fid);
+            Ok(DeletedResponse {
+                gid,
+                fid,
+                deleted_at: tombstone.deleted_at,
+                deleted_by: tombstone.deleted_by.into(),
+            })
+        });
+    paginate(deleted, limit, |d| (d.deleted_at.nanos(), d.gid))
+}
+
+#[cw_serde]
+pub struct DeletedResponse {
+    pub gid: u64,
+    pub fid: Option<String>,
+    pub deleted_at: Timestamp,
+    pub deleted_by: String,
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 100;
 const DEFAULT_LIMIT: u32 = 50;
 
-pub fn query_cyberlinks_by_gids(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, CyberlinkState)>> {
-    let start = start_after.map(Bound::exclusive);
+// A page of a list query, next_cursor is set when more items follow in the same order
+#[cw_serde]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<Binary>,
+}
+
+// Cursors are opaque to clients, they hold the index key of the last returned item
+fn decode_cursor<K: DeserializeOwned>(cursor: Option<Binary>) -> StdResult<Option<K>> {
+    cursor
+        .map(|c| from_json(&c).map_err(|_| StdError::generic_err("Invalid cursor")))
+        .transpose()
+}
+
+// Takes a page of items and, when the range continues, a cursor after the last one
+fn paginate<T, K: Serialize>(
+    items: impl Iterator<Item = StdResult<T>>,
+    limit: Option<u32>,
+    cursor_key: impl Fn(&T) -> K,
+) -> StdResult<Page<T>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut items = items.take(limit + 1).collect::<StdResult<Vec<_>>>()?;
+    let next_cursor = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|item| to_json_binary(&cursor_key(item))).transpose()?
+    } else {
+        None
+    };
+    Ok(Page { items, next_cursor })
+}
+
+// Cyberlinks read per page of a filtered query
+const MAX_FILTER_SCAN: usize = 1000;
+
+// Like paginate, keeping only cyberlinks whose value matches the filter. When the scan budget
+// runs out first, the page may come back short with a cursor after the last cyberlink read
+fn paginate_filtered<K: Serialize>(
+    items: impl Iterator<Item = StdResult<(u64, CyberlinkState)>>,
+    limit: Option<u32>,
+    filter: Option<ValueFilter>,
+    cursor_key: impl Fn(&(u64, CyberlinkState)) -> K,
+) -> StdResult<Page<(u64, CyberlinkState)>> {
+    let Some(filter) = filter else {
+        return paginate(items, limit, cursor_key);
+    };
+    if !filter.pointer.is_empty() && !filter.pointer.starts_with('/') {
+        return Err(StdError::generic_err(format!("Invalid JSON pointer: {}", filter.pointer)));
+    }
+
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut page = vec![];
+    let mut last_scanned = None;
+    for (scanned, item) in items.enumerate() {
+        let item = item?;
+        if page.len() == limit {
+            let next_cursor = page.last().map(|item| to_json_binary(&cursor_key(item))).transpose()?;
+            return Ok(Page { items: page, next_cursor });
+        }
+        if scanned == MAX_FILTER_SCAN {
+            let next_cursor = last_scanned.map(|key| to_json_binary(&key)).transpose()?;
+            return Ok(Page { items: page, next_cursor });
+        }
+        last_scanned = Some(cursor_key(&item));
+        if value_matches(&filter, &item.1.value) {
+            page.push(item);
+        }
+    }
+    Ok(Page { items: page, next_cursor: None })
+}
+
+fn value_matches(filter: &ValueFilter, value: &str) -> bool {
+    let Ok(document) = serde_json::from_str::<serde_json::Value>(value) else {
+        return false;
+    };
+    let Some(field) = document.pointer(&filter.pointer) else {
+        return false;
+    };
+    match (&filter.condition, field) {
+        (ValueCondition::Equals(expected), serde_json::Value::String(s)) => s == expected,
+        (ValueCondition::Equals(expected), other) => serde_json::to_string(other).is_ok_and(|s| s == *expected),
+        (ValueCondition::Prefix(prefix), serde_json::Value::String(s)) => s.starts_with(prefix.as_str()),
+        (ValueCondition::Prefix(_), _) => false,
+        (condition, serde_json::Value::Number(n)) => {
+            let Ok(n) = SignedDecimal::from_str(&n.to_string()) else {
+                return false;
+            };
+            match condition {
+                ValueCondition::Gt(bound) => n > *bound,
+                ValueCondition::Gte(bound) => n >= *bound,
+                ValueCondition::Lt(bound) => n < *bound,
+                ValueCondition::Lte(bound) => n <= *bound,
+                ValueCondition::Equals(_) | ValueCondition::Prefix(_) => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+// Lower and upper bounds of an index range
+type Bounds<'a, K> = (Option<Bound<'a, K>>, Option<Bound<'a, K>>);
+
+// Range bounds continuing after the cursor in the given order
+fn cursor_bounds<'a, K: PrimaryKey<'a>>(cursor: Option<K>, order: Order) -> Bounds<'a, K> {
+    match (cursor, order) {
+        (None, _) => (None, None),
+        (Some(cursor), Order::Ascending) => (Some(Bound::exclusive(cursor)), None),
+        (Some(cursor), Order::Descending) => (None, Some(Bound::exclusive(cursor))),
+    }
+}
+
+// Range bounds of keys strictly after `after`, continuing after the cursor in the given order
+fn since_bounds<'a, K: PrimaryKey<'a> + PartialOrd>(after: Option<K>, cursor: Option<K>, order: Order) -> Bounds<'a, K> {
+    match (cursor, order) {
+        (Some(cursor), Order::Ascending) if after.as_ref().is_none_or(|after| cursor > *after) => (Some(Bound::exclusive(cursor)), None),
+        (Some(cursor), Order::Descending) => (after.map(Bound::exclusive), Some(Bound::exclusive(cursor))),
+        _ => (after.map(Bound::exclusive), None),
+    }
+}
+
+// Range bounds of (time, gid) keys within [start, end], continuing after the cursor in the given order
+fn window_bounds<'a>(
+    start: Timestamp,
+    end: Timestamp,
+    cursor: Option<(u64, u64)>,
+    order: Order,
+) -> Bounds<'a, (u64, u64)> {
+    let low = (start.nanos(), 0);
+    let high = (end.nanos(), u64::MAX);
+    match (cursor, order) {
+        (Some(cursor), Order::Ascending) if cursor >= low => (Some(Bound::exclusive(cursor)), Some(Bound::inclusive(high))),
+        (Some(cursor), Order::Descending) if cursor <= high => (Some(Bound::inclusive(low)), Some(Bound::exclusive(cursor))),
+        _ => (Some(Bound::inclusive(low)), Some(Bound::inclusive(high))),
+    }
+}
+
+// Time of the updated_at indexes, which fall back to created_at
+fn updated_time(c: &CyberlinkState) -> u64 {
+    c.updated_at.map_or(c.created_at.nanos(), |t| t.nanos())
+}
+
+pub fn query_cyberlinks_by_gids(
+    deps: Deps,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>
+) -> StdResult<Page<(u64, CyberlinkState)>> {
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let (min, max) = cursor_bounds(decode_cursor::<u64>(start_after)?, order);
 
     let cyberlinks = cyberlinks()
-        .range(deps.storage, start, None, Order::Descending)
-        .take(limit)
-        .collect::<StdResult<Vec<_>>>()?;
-    Ok(cyberlinks)
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item));
+    paginate(cyberlinks, limit, |(gid, _)| *gid)
 }
 
-pub fn query_cyberlinks_by_owner(deps: Deps, owner: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, CyberlinkState)>> {
+pub fn query_cyberlinks_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>
+) -> StdResult<Page<(u64, CyberlinkState)>> {
     let owner_addr = deps.api.addr_validate(&owner)?;
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let (min, max) = cursor_bounds(decode_cursor::<u64>(start_after)?, order);
 
     // Use the owner index to query cyberlinks by owner
-    let cyberlinks: StdResult<Vec<_>> = cyberlinks()
+    let cyberlinks = cyberlinks()
         .idx
         .owner
         .prefix(owner_addr)
-        .range(
-            deps.storage,
-            start_after.map(Bound::exclusive),
-            None,
-            Order::Descending,
-        )
-        .take(limit)
-        .collect();
-
-    cyberlinks
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item));
+    paginate(cyberlinks, limit, |(gid, _)| *gid)
 }
 
-pub fn query_cyberlinks_by_type(deps: Deps, type_: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, CyberlinkState)>> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(Bound::exclusive);
+pub fn query_cyberlinks_by_type(
+    deps: Deps,
+    type_: String,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>,
+    filter: Option<ValueFilter>,
+) -> StdResult<Page<(u64, CyberlinkState)>> {
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let (min, max) = cursor_bounds(decode_cursor::<u64>(start_after)?, order);
 
-    cyberlinks()
+    let cyberlinks = cyberlinks()
         .idx
         .type_
         .prefix(type_)
-        .range(
-            deps.storage,
-            start,
-            None,
-            Order::Descending,
-        )
-        .take(limit)
-        .collect()
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item));
+    paginate_filtered(cyberlinks, limit, filter, |(gid, _)| *gid)
 }
 
-pub fn query_cyberlinks_by_from(deps: Deps, from: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, CyberlinkState)>> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(Bound::exclusive);
+pub fn query_cyberlinks_by_from(
+    deps: Deps,
+    from: String,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>,
+    filter: Option<ValueFilter>,
+) -> StdResult<Page<(u64, CyberlinkState)>> {
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let (min, max) = cursor_bounds(decode_cursor::<u64>(start_after)?, order);
 
-    cyberlinks()
+    let cyberlinks = cyberlinks()
         .idx
         .from
         .prefix(from)
-        .range(
-            deps.storage,
-            start,
-            None,
-            Order::Descending,
-        )
-        .take(limit)
-        .collect()
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item));
+    paginate_filtered(cyberlinks, limit, filter, |(gid, _)| *gid)
 }
 
-pub fn query_cyberlinks_by_to(deps: Deps, to: String, start_after: Option<u64>, limit: Option<u32>) -> StdResult<Vec<(u64, CyberlinkState)>> {
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(Bound::exclusive);
+pub fn query_cyberlinks_by_to(
+    deps: Deps,
+    to: String,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>,
+    filter: Option<ValueFilter>,
+) -> StdResult<Page<(u64, CyberlinkState)>> {
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let (min, max) = cursor_bounds(decode_cursor::<u64>(start_after)?, order);
 
-    cyberlinks()
+    let cyberlinks = cyberlinks()
         .idx
         .to
         .prefix(to)
-        .range(
-            deps.storage,
-            start,
-            None,
-            Order::Descending,
-        )
-        .take(limit)
-        .collect()
-}
-
-pub fn query_cyberlinks_by_fids(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<Vec<(String, CyberlinkState)>> {
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item));
+    paginate_filtered(cyberlinks, limit, filter, |(gid, _)| *gid)
+}
+
+pub fn query_cyberlinks_by_fids(
+    deps: Deps,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>
+) -> StdResult<Page<(String, CyberlinkState)>> {
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let (min, max) = match (decode_cursor::<String>(start_after)?, order) {
+        (None, _) => (None, None),
+        (Some(fid), Order::Ascending) => (Some(Bound::ExclusiveRaw(fid.into_bytes())), None),
+        (Some(fid), Order::Descending) => (None, Some(Bound::ExclusiveRaw(fid.into_bytes()))),
+    };
+
+    let results = named_cyberlinks(deps, min, max, order, include_deleted);
+    paginate(results, limit, |(fid, _)| fid.clone())
+}
+
+pub fn query_cyberlinks_by_fid_prefix(
+    deps: Deps,
+    prefix: String,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>
+) -> StdResult<Page<(String, CyberlinkState)>> {
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let cursor = decode_cursor::<String>(start_after)?;
+
+    // Keys are compared bytewise, which for UTF-8 is the order of the characters
+    let prefix = prefix.into_bytes();
+    let mut min = Some(Bound::InclusiveRaw(prefix.clone()));
+    let mut max = prefix_end(&prefix).map(Bound::ExclusiveRaw);
+    match (cursor, order) {
+        (None, _) => {}
+        (Some(fid), Order::Ascending) => {
+            if fid.as_bytes() >= prefix.as_slice() {
+                min = Some(Bound::ExclusiveRaw(fid.into_bytes()));
+            }
+        }
+        (Some(fid), Order::Descending) => {
+            if prefix_end(&prefix).is_none_or(|end| fid.as_bytes() < end.as_slice()) {
+                max = Some(Bound::ExclusiveRaw(fid.into_bytes()));
+            }
+        }
+    }
+
+    let results = named_cyberlinks(deps, min, max, order, include_deleted);
+    paginate(results, limit, |(fid, _)| fid.clone())
+}
+
+// Smallest key after every key starting with the prefix, none if no such key exists
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
 
-    let results = NAMED_CYBERLINKS
-        .range(deps.storage, start, None, Order::Descending)
-        .take(limit)
-        .map(|item| -> StdResult<Option<(String, CyberlinkState)>> {
+// Named cyberlinks within the bounds with their state
+fn named_cyberlinks<'a>(
+    deps: Deps<'a>,
+    min: Option<Bound<'a, &'a str>>,
+    max: Option<Bound<'a, &'a str>>,
+    order: Order,
+    include_deleted: bool,
+) -> impl Iterator<Item = StdResult<(String, CyberlinkState)>> + 'a {
+    NAMED_CYBERLINKS
+        .range(deps.storage, min, max, order)
+        .map(move |item| -> StdResult<Option<(String, CyberlinkState)>> {
             let (fid, gid) = item?;
-            if DELETED_GIDS.has(deps.storage, gid) {
+            if !include_deleted && DELETED_GIDS.has(deps.storage, gid) {
                 return Ok(None); // Skip deleted
             }
             match cyberlinks().may_load(deps.storage, gid)? {
@@ -159,9 +499,6 @@ pub fn query_cyberlinks_by_fids(deps: Deps, start_after: Option<String>, limit:
             }
         })
         .filter_map(Result::transpose) // Filter out None values and propagate Err
-        .collect::<StdResult<Vec<_>>>()?;
-
-    Ok(results)
 }
 
 pub fn query_cyberlinks_set_by_gids(deps: Deps, ids: Vec<u64>) -> StdResult<Vec<(u64, CyberlinkState)>> {
@@ -183,113 +520,351 @@ pub fn query_cyberlinks_set_by_gids(deps: Deps, ids: Vec<u64>) -> StdResult<Vec<
     Ok(links)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn query_cyberlinks_by_owner_time(
     deps: Deps,
     env: Env,
     owner: String,
     start_time: Timestamp,
     end_time: Option<Timestamp>,
-    start_after: Option<u64>,
-    limit: Option<u32>
-) -> StdResult<Vec<(u64, CyberlinkState)>> {
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>
+) -> StdResult<Page<(u64, CyberlinkState)>> {
     let owner_addr = deps.api.addr_validate(&owner)?;
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
     // Use current block time if end_time is not provided
     let end = end_time.unwrap_or(env.block.time);
-    
-    // Convert Timestamp to u64 (nanos)
-    let start_nanos = start_time.nanos();
-    let end_nanos = end.nanos();
-    
-    // Query using the created_at index with bounds
+    let (min, max) = window_bounds(start_time, end, decode_cursor(start_after)?, order);
+
+    // Query using the created_at index, the cursor is the (created_at, gid) of the last item
     let cyberlinks = cyberlinks()
         .idx
         .created_at
-        // Use sub_prefix with just the owner (first part of composite key)
         .sub_prefix(owner_addr)
-        .range(
-            deps.storage,
-            // Use bounds on just the timestamp part
-            Some(Bound::exclusive((start_nanos, start_after.unwrap_or(0u64)))),
-            Some(Bound::inclusive((end_nanos, u64::MAX))),
-            Order::Descending,
-        )
-        .take(limit)
-        .collect::<StdResult<Vec<_>>>()?;
-    
-    Ok(cyberlinks)
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item));
+    paginate(cyberlinks, limit, |(gid, c)| (c.created_at.nanos(), *gid))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn query_cyberlinks_by_owner_time_any(
     deps: Deps,
     env: Env,
     owner: String,
     start_time: Timestamp,
     end_time: Option<Timestamp>,
-    start_after: Option<u64>,
-    limit: Option<u32>
-) -> StdResult<Vec<(u64, CyberlinkState)>> {
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>
+) -> StdResult<Page<(u64, CyberlinkState)>> {
     let owner_addr = deps.api.addr_validate(&owner)?;
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
     // Use current block time if end_time is not provided
     let end = end_time.unwrap_or(env.block.time);
-    
-    // Convert Timestamp to u64 (nanos)
-    let start_nanos = start_time.nanos();
-    let end_nanos = end.nanos();
-    
-    // Get cyberlinks by created_at time
-    let created_cyberlinks = cyberlinks()
+    let (min, max) = window_bounds(start_time, end, decode_cursor(start_after)?, order);
+
+    // Cyberlinks created within the window, keyed by created_at
+    let mut created = cyberlinks()
         .idx
         .created_at
         .sub_prefix(owner_addr.clone())
-        .range(
-            deps.storage,
-            Some(Bound::exclusive((start_nanos, start_after.unwrap_or(0u64)))),
-            Some(Bound::inclusive((end_nanos, u64::MAX))),
-            Order::Descending,
-        )
-        .take(limit)
-        .collect::<StdResult<Vec<_>>>()?;
-    
-    // Get cyberlinks by updated_at time
-    let updated_cyberlinks = cyberlinks()
+        .range(deps.storage, min.clone(), max.clone(), order)
+        .filter(|item| include_deleted || is_live(deps.storage, item))
+        .peekable();
+
+    // Cyberlinks created before the window and updated within it, keyed by updated_at,
+    // so that every cyberlink comes from exactly one of the two streams
+    let mut updated = cyberlinks()
         .idx
         .updated_at
         .sub_prefix(owner_addr)
-        .range(
-            deps.storage,
-            Some(Bound::exclusive((start_nanos, start_after.unwrap_or(0u64)))),
-            Some(Bound::inclusive((end_nanos, u64::MAX))),
-            Order::Descending,
-        )
-        .take(limit)
-        .collect::<StdResult<Vec<_>>>()?;
-    
-    // Merge and deduplicate the results
-    let mut all_cyberlinks = created_cyberlinks;
-    
-    // Add cyberlinks from updated_at if they're not already in the list
-    for (id, cyberlink) in updated_cyberlinks {
-        if !all_cyberlinks.iter().any(|(existing_id, _)| *existing_id == id) {
-            all_cyberlinks.push((id, cyberlink));
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item))
+        .filter(|item| item.as_ref().map_or(true, |(_, c)| c.created_at < start_time))
+        .peekable();
+
+    // Merge both streams by their (time, gid) keys in the requested order
+    let any_time = move |c: &CyberlinkState| {
+        if c.created_at >= start_time { c.created_at.nanos() } else { updated_time(c) }
+    };
+    let merged = std::iter::from_fn(move || {
+        let next_created = match created.peek() {
+            Some(Ok((gid, c))) => Some((c.created_at.nanos(), *gid)),
+            Some(Err(_)) => return created.next(),
+            None => None,
+        };
+        let next_updated = match updated.peek() {
+            Some(Ok((gid, c))) => Some((updated_time(c), *gid)),
+            Some(Err(_)) => return updated.next(),
+            None => None,
+        };
+        match (next_created, next_updated) {
+            (Some(c), Some(u)) => {
+                let created_first = match order {
+                    Order::Ascending => c < u,
+                    Order::Descending => c > u,
+                };
+                if created_first { created.next() } else { updated.next() }
+            }
+            (Some(_), None) => created.next(),
+            (None, Some(_)) => updated.next(),
+            (None, None) => None,
+        }
+    });
+    paginate(merged, limit, |(gid, c)| (any_time(c), *gid))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn query_cyberlinks_by_type_time(
+    deps: Deps,
+    env: Env,
+    type_: String,
+    start_time: Timestamp,
+    end_time: Option<Timestamp>,
+    time_field: Option<TimeField>,
+    order: Option<Order>,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>,
+) -> StdResult<Page<(u64, CyberlinkState)>> {
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let end = end_time.unwrap_or(env.block.time);
+    let (min, max) = window_bounds(start_time, end, decode_cursor(start_after)?, order);
+
+    let time_field = time_field.unwrap_or(TimeField::Created);
+    let index = match time_field {
+        TimeField::Created => cyberlinks().idx.type_created_at,
+        TimeField::Updated => cyberlinks().idx.type_updated_at,
+    };
+    let cyberlinks = index
+        .sub_prefix(type_)
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item));
+    paginate(cyberlinks, limit, |(gid, c)| {
+        let time = match time_field {
+            TimeField::Created => c.created_at.nanos(),
+            TimeField::Updated => updated_time(c),
+        };
+        (time, *gid)
+    })
+}
+
+// Link types that put a post on the author's timeline
+const FEED_LINK_TYPES: [&str; 2] = ["Posts", "Reposts"];
+// Author and link type streams merged per page, followees are capped so that each one
+// brings all of its feed types
+const MAX_FEED_STREAMS: usize = 400;
+
+type LinkStream<'a> = Box<dyn Iterator<Item = StdResult<(u64, CyberlinkState)>> + 'a>;
+
+pub fn query_feed(
+    deps: Deps,
+    account_fid: String,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> StdResult<Page<(u64, CyberlinkState)>> {
+    let gid = NAMED_CYBERLINKS.load(deps.storage, &account_fid)?;
+    if DELETED_GIDS.has(deps.storage, gid) {
+        return Err(deleted_error(deps.storage, gid));
+    }
+
+    // Link types of a kind, including the ones of namespaced semantic cores
+    let types_of = |kinds: &[&str]| {
+        TYPES
+            .keys(deps.storage, None, None, Order::Ascending)
+            .filter(|type_| type_.as_ref().map_or(true, |t| kinds.contains(&base_type_name(t))))
+            .collect::<StdResult<Vec<String>>>()
+    };
+    let feed_types = types_of(&FEED_LINK_TYPES)?;
+
+    // Accounts the viewer follows, read from its own Follows links only, most recently followed first
+    let max_followees = MAX_FEED_STREAMS / feed_types.len().max(1);
+    let follows: Vec<LinkStream> = types_of(&["Follows"])?
+        .into_iter()
+        .map(|follows_type| -> LinkStream {
+            Box::new(cyberlinks()
+                .idx
+                .from_type_created_at
+                .sub_prefix((account_fid.clone(), follows_type))
+                .range(deps.storage, None, None, Order::Descending)
+                .filter(move |item| is_live(deps.storage, item)))
+        })
+        .collect();
+    let mut followees = vec![];
+    let mut seen = BTreeSet::new();
+    for item in merge_newest_first(follows)? {
+        if followees.len() >= max_followees {
+            break;
+        }
+        let (_, follow) = item?;
+        if seen.insert(follow.to.clone()) {
+            followees.push(follow.to);
+        }
+    }
+
+    // One newest-first stream per author and link type, skipping posts that were deleted
+    let (_, max) = cursor_bounds(decode_cursor::<(u64, u64)>(start_after)?, Order::Descending);
+    let mut streams: Vec<LinkStream> = vec![];
+    for author in &followees {
+        for type_ in &feed_types {
+            let stream = cyberlinks()
+                .idx
+                .from_type_created_at
+                .sub_prefix((author.clone(), type_.clone()))
+                .range(deps.storage, None, max.clone(), Order::Descending)
+                .filter(move |item| is_live(deps.storage, item))
+                .filter(move |item| item.as_ref().map_or(true, |(_, c)| is_live_fid(deps.storage, &c.to)));
+            streams.push(Box::new(stream));
+        }
+    }
+
+    paginate(merge_newest_first(streams)?, limit, |(gid, c)| (c.created_at.nanos(), *gid))
+}
+
+fn is_live_fid(storage: &dyn Storage, fid: &str) -> bool {
+    match NAMED_CYBERLINKS.may_load(storage, fid) {
+        Ok(Some(gid)) => !DELETED_GIDS.has(storage, gid),
+        _ => false,
+    }
+}
+
+// K-way merge of streams that are each ordered newest first, by (created_at, gid)
+fn merge_newest_first<'a>(
+    mut streams: Vec<LinkStream<'a>>,
+) -> StdResult<impl Iterator<Item = StdResult<(u64, CyberlinkState)>> + 'a> {
+    let mut heads = vec![None; streams.len()];
+    let mut heap = BinaryHeap::new();
+    for (i, stream) in streams.iter_mut().enumerate() {
+        if let Some(item) = stream.next() {
+            let (gid, c) = item?;
+            heap.push((c.created_at.nanos(), gid, i));
+            heads[i] = Some((gid, c));
+        }
+    }
+
+    Ok(std::iter::from_fn(move || {
+        let (_, _, i) = heap.pop()?;
+        let head = heads[i].take()?;
+        match streams[i].next() {
+            Some(Ok((gid, c))) => {
+                heap.push((c.created_at.nanos(), gid, i));
+                heads[i] = Some((gid, c));
+            }
+            Some(Err(err)) => return Some(Err(err)),
+            None => {}
+        }
+        Some(Ok(head))
+    }))
+}
+
+// Caps on graph walks, keeping recommendation queries within the query gas limit
+const MAX_CONNECTIONS: usize = 200; // first-degree neighbors considered per node
+const MAX_EDGE_SCAN: usize = 5000; // links read across a whole walk
+
+#[cw_serde]
+pub struct ConnectionSuggestion {
+    pub fid: String,
+    // Number of first-degree neighbors linking to the suggested node
+    pub shared: u32,
+}
+
+#[cw_serde]
+pub struct SuggestConnectionsResponse {
+    pub suggestions: Vec<ConnectionSuggestion>,
+    // Set when a cap cut the walk short, the suggestions are then based on part of the graph
+    pub truncated: bool,
+}
+
+#[cw_serde]
+pub struct MutualConnectionsResponse {
+    pub connections: Vec<String>,
+    pub truncated: bool,
+}
+
+pub fn query_suggest_connections(deps: Deps, fid: String, link_type: String, limit: Option<u32>) -> StdResult<SuggestConnectionsResponse> {
+    check_connection_type(deps, &link_type)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let mut budget = MAX_EDGE_SCAN;
+    let (first, mut truncated) = connections(deps.storage, &fid, &link_type, &mut budget)?;
+    let mut shared: BTreeMap<String, u32> = BTreeMap::new();
+    for neighbor in &first {
+        let (second, cut) = connections(deps.storage, neighbor, &link_type, &mut budget)?;
+        truncated |= cut;
+        for candidate in second {
+            if candidate != fid && !first.contains(&candidate) {
+                *shared.entry(candidate).or_default() += 1;
+            }
+        }
+    }
+
+    let mut suggestions = shared
+        .into_iter()
+        .map(|(fid, shared)| ConnectionSuggestion { fid, shared })
+        .collect::<Vec<_>>();
+    suggestions.sort_by(|a, b| b.shared.cmp(&a.shared).then_with(|| a.fid.cmp(&b.fid)));
+    suggestions.truncate(limit);
+
+    Ok(SuggestConnectionsResponse { suggestions, truncated })
+}
+
+pub fn query_mutual_connections(deps: Deps, a: String, b: String, link_type: String) -> StdResult<MutualConnectionsResponse> {
+    check_connection_type(deps, &link_type)?;
+
+    let mut budget = MAX_EDGE_SCAN;
+    let (a_connections, a_truncated) = connections(deps.storage, &a, &link_type, &mut budget)?;
+    let (b_connections, b_truncated) = connections(deps.storage, &b, &link_type, &mut budget)?;
+
+    Ok(MutualConnectionsResponse {
+        connections: a_connections.intersection(&b_connections).cloned().collect(),
+        truncated: a_truncated || b_truncated,
+    })
+}
+
+// Connections are links between nodes of one type, e.g. Follows from Account to Account
+fn check_connection_type(deps: Deps, link_type: &str) -> StdResult<()> {
+    let gid = NAMED_CYBERLINKS.may_load(deps.storage, link_type)?
+        .ok_or_else(|| StdError::not_found(format!("type {}", link_type)))?;
+    let definition = cyberlinks().load(deps.storage, gid)?;
+    if definition.type_ != "Type" || definition.from == "Any" || definition.from != definition.to {
+        return Err(StdError::generic_err(format!("Not a link type between nodes of the same type: {}", link_type)));
+    }
+    Ok(())
+}
+
+// Live nodes a node links to with a link type, reading links while the budget lasts;
+// the flag is set when links were left unread
+fn connections(storage: &dyn Storage, fid: &str, link_type: &str, budget: &mut usize) -> StdResult<(BTreeSet<String>, bool)> {
+    let mut found = BTreeSet::new();
+    let links = cyberlinks()
+        .idx
+        .from_type_created_at
+        .sub_prefix((fid.to_string(), link_type.to_string()))
+        .range(storage, None, None, Order::Ascending);
+    for item in links {
+        if *budget == 0 || found.len() >= MAX_CONNECTIONS {
+            return Ok((found, true));
+        }
+        *budget -= 1;
+        let (gid, link) = item?;
+        if !DELETED_GIDS.has(storage, gid) && link.to != fid && is_live_fid(storage, &link.to) {
+            found.insert(link.to);
         }
     }
-    
-    // Sort by ID for consistent results
-    all_cyberlinks.sort_by(|a, b| a.0.cmp(&b.0));
-    
-    // Apply limit
-    let result = all_cyberlinks.into_iter().take(limit).collect();
-    
-    Ok(result)
+    Ok((found, false))
 }
 
 pub fn query_cyberlink_by_fid(deps: Deps, fid: String) -> StdResult<CyberlinkState> {
     // First try to load directly from NAMED_CYBERLINKS
     let gid = NAMED_CYBERLINKS.load(deps.storage, &fid)?;
     if DELETED_GIDS.has(deps.storage, gid) {
-        return Err(StdError::not_found("deleted cyberlink"));
+        return Err(deleted_error(deps.storage, gid));
     }
 
     let cyberlink_state = cyberlinks().load(deps.storage, gid)?;
@@ -297,6 +872,48 @@ pub fn query_cyberlink_by_fid(deps: Deps, fid: String) -> StdResult<CyberlinkSta
     Ok(cyberlink_state)
 }
 
+pub fn query_acl(deps: Deps, fid: String) -> StdResult<AclResponse> {
+    let gid = NAMED_CYBERLINKS.load(deps.storage, &fid)?;
+    if DELETED_GIDS.has(deps.storage, gid) {
+        return Err(StdError::not_found("deleted cyberlink"));
+    }
+    let cyberlink = cyberlinks().load(deps.storage, gid)?;
+    let acl = NODE_ACL.may_load(deps.storage, gid)?.unwrap_or_default();
+    Ok(AclResponse {
+        fid,
+        owner: cyberlink.owner.into(),
+        editors: acl.editors.into_iter().map(|a| a.into()).collect(),
+        deleters: acl.deleters.into_iter().map(|a| a.into()).collect(),
+    })
+}
+
+#[cw_serde]
+pub struct AclResponse {
+    pub fid: String,
+    pub owner: String,
+    pub editors: Vec<String>,
+    pub deleters: Vec<String>,
+}
+
+pub fn query_pending_transfer(deps: Deps, fid: String) -> StdResult<PendingTransferResponse> {
+    let gid = NAMED_CYBERLINKS.load(deps.storage, &fid)?;
+    let new_owner = PENDING_TRANSFERS.may_load(deps.storage, gid)?
+        .ok_or_else(|| StdError::not_found(format!("pending transfer of {}", fid)))?;
+    let cyberlink = cyberlinks().load(deps.storage, gid)?;
+    Ok(PendingTransferResponse {
+        fid,
+        owner: cyberlink.owner.into(),
+        new_owner: new_owner.into(),
+    })
+}
+
+#[cw_serde]
+pub struct PendingTransferResponse {
+    pub fid: String,
+    pub owner: String,
+    pub new_owner: String,
+}
+
 pub fn query_cyberlinks_set_by_fids(deps: Deps, fids: Vec<String>) -> StdResult<Vec<(String, CyberlinkState)>> {
     let mut links: Vec<(String, CyberlinkState)> = vec![];
 
@@ -327,26 +944,52 @@ pub fn query_cyberlinks_by_owner_and_type(
     deps: Deps,
     owner: String,
     type_: String,
-    start_after: Option<u64>,
-    limit: Option<u32>
-) -> StdResult<Vec<(u64, CyberlinkState)>> {
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>
+) -> StdResult<Page<(u64, CyberlinkState)>> {
     let owner_addr = deps.api.addr_validate(&owner)?;
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(Bound::exclusive);
+    let order = order.unwrap_or(Order::Descending);
+    let include_deleted = include_deleted.unwrap_or(false);
+    let (min, max) = cursor_bounds(decode_cursor::<u64>(start_after)?, order);
 
-    cyberlinks()
+    let cyberlinks = cyberlinks()
         .idx
         .owner_type
         // Use prefix for the composite key (owner_addr, type_)
         .prefix((owner_addr, type_))
-        .range(
-            deps.storage,
-            start, // The start_after (u64) refers to the primary key (GID)
-            None,
-            Order::Descending,
-        )
-        .take(limit)
-        .collect()
+        .range(deps.storage, min, max, order)
+        .filter(|item| include_deleted || is_live(deps.storage, item));
+    paginate(cyberlinks, limit, |(gid, _)| *gid)
+}
+
+pub fn query_cyberlinks_by_field(
+    deps: Deps,
+    type_: String,
+    field: String,
+    value: String,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+) -> StdResult<Page<(u64, CyberlinkState)>> {
+    let indexed = TYPES
+        .may_load(deps.storage, &type_)?
+        .is_some_and(|meta| meta.indexed_fields.contains(&field));
+    if !indexed {
+        return Err(StdError::generic_err(format!("Field '{}' is not indexed for type '{}'", field, type_)));
+    }
+    let order = order.unwrap_or(Order::Descending);
+    let (min, max) = cursor_bounds(decode_cursor::<u64>(start_after)?, order);
+
+    let cyberlinks = FIELD_INDEX
+        .prefix(((type_.as_str(), field.as_str()), value.as_str()))
+        .keys(deps.storage, min, max, order)
+        .map(|gid| {
+            let gid = gid?;
+            Ok((gid, cyberlinks().load(deps.storage, gid)?))
+        });
+    paginate(cyberlinks, limit, |(gid, _)| *gid)
 }
 
 #[cw_serde]
@@ -379,11 +1022,393 @@ pub fn query_graph_stats(
     }
 
     // Load owner-type count if both owner and type are specified
-    if let (Some(ref owner_addr), Some(ref type_str)) = (owner_addr_opt, type_.as_ref()) {
+    if let (Some(ref owner_addr), Some(type_str)) = (owner_addr_opt, type_.as_ref()) {
         response.owner_type_count = OWNER_TYPE_LINK_COUNT
             .may_load(deps.storage, (owner_addr, type_str))?
             .map(Uint64::new);
     }
 
     Ok(response)
-}
\ No newline at end of file
+}
+
+pub fn query_node_stats(deps: Deps, fid: String) -> StdResult<NodeStatsResponse> {
+    let gid = NAMED_CYBERLINKS.load(deps.storage, &fid)?;
+    if DELETED_GIDS.has(deps.storage, gid) {
+        return Err(deleted_error(deps.storage, gid));
+    }
+
+    let by_type = |map: Map<(&str, &str), u64>| -> StdResult<Vec<(String, Uint64)>> {
+        map.prefix(fid.as_str())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(type_, count)| (type_, Uint64::new(count))))
+            .collect()
+    };
+
+    Ok(NodeStatsResponse {
+        in_degree: Uint64::new(IN_DEGREE.may_load(deps.storage, &fid)?.unwrap_or(0)),
+        out_degree: Uint64::new(OUT_DEGREE.may_load(deps.storage, &fid)?.unwrap_or(0)),
+        in_by_type: by_type(IN_DEGREE_BY_TYPE)?,
+        out_by_type: by_type(OUT_DEGREE_BY_TYPE)?,
+        fid,
+    })
+}
+
+pub fn query_reactions(deps: Deps, target_fid: String) -> StdResult<ReactionsResponse> {
+    let tallies = REACTION_TALLIES
+        .prefix(target_fid.as_str())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(value, count)| (value, Uint64::new(count))))
+        .collect::<StdResult<Vec<_>>>()?;
+    let total = tallies.iter().map(|(_, count)| *count).sum();
+
+    Ok(ReactionsResponse { target_fid, total, tallies })
+}
+
+// Caps on pattern matching, a query that exceeds them fails instead of returning partial results
+const MAX_MATCH_PATTERNS: usize = 5;
+const MAX_MATCH_ROWS: usize = 1000; // bindings kept after each pattern
+const MAX_MATCH_SCAN: usize = 5000; // links read across all patterns
+
+type Bindings = BTreeMap<String, String>;
+
+pub fn query_match(
+    deps: Deps,
+    patterns: Vec<TriplePattern>,
+    start_after: Option<Binary>,
+    limit: Option<u32>,
+) -> StdResult<Page<Bindings>> {
+    if patterns.is_empty() || patterns.len() > MAX_MATCH_PATTERNS {
+        return Err(StdError::generic_err(format!("Match takes 1 to {} patterns", MAX_MATCH_PATTERNS)));
+    }
+    if patterns.iter().any(|p| variable(&p.predicate).is_some()) {
+        return Err(StdError::generic_err("Match predicates must be link types"));
+    }
+
+    // Nested loop join, each pattern is looked up with the terms bound so far
+    let mut rows = vec![Bindings::new()];
+    let mut scanned = 0;
+    for pattern in &patterns {
+        let mut next_rows = vec![];
+        for row in &rows {
+            let subject = resolve_term(&pattern.subject, row);
+            let object = resolve_term(&pattern.object, row);
+            for (from, to) in match_links(deps.storage, &pattern.predicate, subject, object, &mut scanned)? {
+                let mut row = row.clone();
+                if bind_term(&mut row, &pattern.subject, from) && bind_term(&mut row, &pattern.object, to) {
+                    next_rows.push(row);
+                }
+            }
+            if next_rows.len() > MAX_MATCH_ROWS {
+                return Err(StdError::generic_err(format!("Match exceeds {} intermediate results", MAX_MATCH_ROWS)));
+            }
+        }
+        rows = next_rows;
+    }
+
+    // Rows are ordered so that the cursor is the last row returned. There is no join position
+    // to resume from, every page runs the whole join again within the same bounds
+    rows.sort();
+    rows.dedup();
+    let cursor = decode_cursor::<Bindings>(start_after)?;
+    let rows = rows
+        .into_iter()
+        .filter(|row| cursor.as_ref().is_none_or(|cursor| row > cursor))
+        .map(Ok);
+    paginate(rows, limit, |row| row.clone())
+}
+
+fn variable(term: &str) -> Option<&str> {
+    term.strip_prefix('?')
+}
+
+// The value of a constant or of a bound variable
+fn resolve_term<'a>(term: &'a str, row: &'a Bindings) -> Option<&'a str> {
+    match variable(term) {
+        Some(name) => row.get(name).map(String::as_str),
+        None => Some(term),
+    }
+}
+
+// Binds a variable to the value, failing when it is already bound to another one
+fn bind_term(row: &mut Bindings, term: &str, value: String) -> bool {
+    match variable(term) {
+        Some(name) => match row.get(name) {
+            Some(bound) => *bound == value,
+            None => {
+                row.insert(name.to_string(), value);
+                true
+            }
+        },
+        None => term == value,
+    }
+}
+
+// (from, to) of the live links of a type, narrowed by the known ends
+fn match_links(
+    storage: &dyn Storage,
+    type_: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+    scanned: &mut usize,
+) -> StdResult<Vec<(String, String)>> {
+    let links: LinkStream = match (from, to) {
+        (Some(from), _) => Box::new(cyberlinks()
+            .idx
+            .from_type_created_at
+            .sub_prefix((from.to_string(), type_.to_string()))
+            .range(storage, None, None, Order::Ascending)),
+        (None, Some(to)) => Box::new(cyberlinks()
+            .idx
+            .to
+            .prefix(to.to_string())
+            .range(storage, None, None, Order::Ascending)),
+        (None, None) => Box::new(cyberlinks()
+            .idx
+            .type_
+            .prefix(type_.to_string())
+            .range(storage, None, None, Order::Ascending)),
+    };
+
+    let mut matches = vec![];
+    for item in links {
+        *scanned += 1;
+        if *scanned > MAX_MATCH_SCAN {
+            return Err(StdError::generic_err(format!("Match exceeds {} scanned cyberlinks", MAX_MATCH_SCAN)));
+        }
+        let (gid, link) = item?;
+        if DELETED_GIDS.has(storage, gid) || link.type_ != type_ || to.is_some_and(|to| link.to != to) {
+            continue;
+        }
+        // Live links may still point at deleted or purged nodes, like in Feed and Connections
+        if !is_live_fid(storage, &link.from) || !is_live_fid(storage, &link.to) {
+            continue;
+        }
+        matches.push((link.from, link.to));
+    }
+    Ok(matches)
+}
+
+const DEFAULT_EXPORT_DEPTH: u32 = 1;
+const MAX_EXPORT_DEPTH: u32 = 5;
+const MAX_EXPORT_ENTRIES: usize = 1000; // nodes and edges collected per walk
+const MAX_EXPORT_SCAN: usize = 5000; // links read per walk
+
+#[cw_serde]
+pub struct SubgraphResponse {
+    pub entries: Vec<SemcoreEntry>,
+    pub next_cursor: Option<Binary>,
+    // The walk hit its bounds, the subgraph continues past the exported entries
+    pub truncated: bool,
+}
+
+// Every chunk walks the subgraph again and returns the entries after the cursor, ordered by GID
+pub fn query_export_subgraph(
+    deps: Deps,
+    root_fids: Vec<String>,
+    depth: Option<u32>,
+    link_types: Option<Vec<String>>,
+    cursor: Option<Binary>,
+    limit: Option<u32>,
+) -> StdResult<SubgraphResponse> {
+    let depth = depth.unwrap_or(DEFAULT_EXPORT_DEPTH).min(MAX_EXPORT_DEPTH);
+    let (entries, truncated) = walk_subgraph(deps.storage, &root_fids, depth, link_types.as_deref())?;
+
+    let start = decode_cursor::<u64>(cursor)?;
+    let items = entries
+        .into_iter()
+        .filter(|(gid, _)| start.is_none_or(|start| *gid > start))
+        .map(Ok);
+    let page = paginate(items, limit, |(gid, _)| *gid)?;
+    Ok(SubgraphResponse {
+        entries: page.items.iter().map(|(_, state)| SemcoreEntry::from_state(state)).collect(),
+        next_cursor: page.next_cursor,
+        truncated,
+    })
+}
+
+// Breadth-first walk over live links in both directions, collecting the links and their endpoints
+fn walk_subgraph(
+    storage: &dyn Storage,
+    root_fids: &[String],
+    depth: u32,
+    link_types: Option<&[String]>,
+) -> StdResult<(BTreeMap<u64, CyberlinkState>, bool)> {
+    let mut entries = BTreeMap::new();
+    let mut seen = BTreeSet::new();
+    let mut frontier = vec![];
+    for fid in root_fids {
+        let gid = NAMED_CYBERLINKS.load(storage, fid)?;
+        if DELETED_GIDS.has(storage, gid) {
+            return Err(deleted_error(storage, gid));
+        }
+        entries.insert(gid, cyberlinks().load(storage, gid)?);
+        if seen.insert(fid.clone()) {
+            frontier.push(fid.clone());
+        }
+    }
+
+    let links = cyberlinks();
+    let mut budget = MAX_EXPORT_SCAN;
+    for _ in 0..depth {
+        let mut next = vec![];
+        for fid in &frontier {
+            for (index, outgoing) in [(&links.idx.from, true), (&links.idx.to, false)] {
+                for item in index.prefix(fid.clone()).range(storage, None, None, Order::Ascending) {
+                    if budget == 0 || entries.len() >= MAX_EXPORT_ENTRIES {
+                        return Ok((entries, true));
+                    }
+                    budget -= 1;
+                    let (gid, link) = item?;
+                    if DELETED_GIDS.has(storage, gid) || link_types.is_some_and(|types| !types.contains(&link.type_)) {
+                        continue;
+                    }
+                    let other = if outgoing { link.to.clone() } else { link.from.clone() };
+                    entries.insert(gid, link);
+                    if other == "Any" || !seen.insert(other.clone()) {
+                        continue;
+                    }
+                    if let Some(other_gid) = NAMED_CYBERLINKS.may_load(storage, &other)? {
+                        if !DELETED_GIDS.has(storage, other_gid) {
+                            entries.insert(other_gid, cyberlinks().load(storage, other_gid)?);
+                            next.push(other);
+                        }
+                    }
+                }
+            }
+        }
+        frontier = next;
+    }
+    Ok((entries, false))
+}
+
+#[cw_serde]
+pub struct RankResponse {
+    pub fid: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub score: Decimal,
+}
+
+pub fn query_rank(deps: Deps, fid: String) -> StdResult<RankResponse> {
+    let rank = rank_scores().may_load(deps.storage, &fid)?
+        .ok_or_else(|| StdError::not_found(format!("rank of {}", fid)))?;
+    Ok(RankResponse { fid, type_: rank.type_, score: rank.score })
+}
+
+pub fn query_top_ranked(
+    deps: Deps,
+    type_: String,
+    start_after: Option<Binary>,
+    order: Option<Order>,
+    limit: Option<u32>,
+) -> StdResult<Page<RankResponse>> {
+    // Highest score first by default, the cursor holds the (score, fid) key of the last node returned
+    let order = order.unwrap_or(Order::Descending);
+    let cursor = decode_cursor::<(Uint128, String)>(start_after)?.map(|(score, fid)| (score.u128(), fid));
+    let (min, max) = cursor_bounds(cursor, order);
+
+    let ranks = rank_scores()
+        .idx
+        .type_score
+        .sub_prefix(type_)
+        .range(deps.storage, min, max, order)
+        // Scores of deleted nodes stay until the next run prunes them, keep them out of the ranking
+        .filter(|item| item.as_ref().map_or(true, |(fid, _)| is_live_fid(deps.storage, fid)))
+        .map(|item| item.map(|(fid, rank)| RankResponse { fid, type_: rank.type_, score: rank.score }));
+    paginate(ranks, limit, |r| (Uint128::new(r.score.atomics().u128()), r.fid.clone()))
+}
+
+#[cw_serde]
+pub struct TypeResponse {
+    pub id: String,
+    pub gid: u64,
+    // Constraint on the type of the source node
+    pub from: String,
+    // Constraint on the type of the destination node
+    pub to: String,
+    // Value schema attached to the type definition
+    pub value: String,
+    // Semantic core the type was loaded from, if any
+    pub semcore: Option<String>,
+    pub kind: TypeKind,
+    pub allow_self_loop: bool,
+    pub lifecycle: TypeLifecycle,
+    pub indexed_fields: Vec<String>,
+}
+
+#[cw_serde]
+pub struct TypeInfoResponse {
+    #[serde(rename = "type")]
+    pub type_: TypeResponse,
+    // Link types which may leave nodes of this type
+    pub outgoing: Vec<String>,
+    // Link types which may enter nodes of this type
+    pub incoming: Vec<String>,
+}
+
+fn load_type(deps: Deps, id: &str) -> StdResult<TypeResponse> {
+    let meta = TYPES.load(deps.storage, id)?;
+    let gid = NAMED_CYBERLINKS.load(deps.storage, id)?;
+    let state = cyberlinks().load(deps.storage, gid)?;
+
+    Ok(TypeResponse {
+        id: id.to_string(),
+        gid,
+        from: state.from,
+        to: state.to,
+        value: state.value,
+        semcore: meta.semcore,
+        kind: meta.kind,
+        allow_self_loop: meta.allow_self_loop,
+        lifecycle: meta.lifecycle,
+        indexed_fields: meta.indexed_fields,
+    })
+}
+
+pub fn query_types(deps: Deps, start_after: Option<Binary>, order: Option<Order>, limit: Option<u32>) -> StdResult<Page<TypeResponse>> {
+    let order = order.unwrap_or(Order::Descending);
+    let (min, max) = match (decode_cursor::<String>(start_after)?, order) {
+        (None, _) => (None, None),
+        (Some(id), Order::Ascending) => (Some(Bound::ExclusiveRaw(id.into_bytes())), None),
+        (Some(id), Order::Descending) => (None, Some(Bound::ExclusiveRaw(id.into_bytes()))),
+    };
+
+    let types = TYPES
+        .keys(deps.storage, min, max, order)
+        .map(|id| load_type(deps, &id?));
+    paginate(types, limit, |t| t.id.clone())
+}
+
+pub fn query_type_info(deps: Deps, type_: String) -> StdResult<TypeInfoResponse> {
+    let definition = load_type(deps, &type_)?;
+
+    // Link types are registered edge types whose from/to constraint names this type or Any,
+    // the base types and node types are left out
+    let mut outgoing = vec![];
+    let mut incoming = vec![];
+    for item in TYPES.range(deps.storage, None, None, Order::Ascending) {
+        let (id, meta) = item?;
+        if matches!(id.as_str(), "Type" | "Any") || meta.kind == TypeKind::Node {
+            continue;
+        }
+        let Some(gid) = NAMED_CYBERLINKS.may_load(deps.storage, &id)? else {
+            continue;
+        };
+        if DELETED_GIDS.has(deps.storage, gid) {
+            continue;
+        }
+        let state = cyberlinks().load(deps.storage, gid)?;
+        if state.from == type_ || state.from == "Any" {
+            outgoing.push(id.clone());
+        }
+        if state.to == type_ || state.to == "Any" {
+            incoming.push(id);
+        }
+    }
+
+    Ok(TypeInfoResponse {
+        type_: definition,
+        outgoing,
+        incoming,
+    })
+}
@@ -1,53 +1,120 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use serde_json;
 
+use crate::state::TypeKind;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TypeDefinition {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
     #[serde(rename = "type")]
     pub type_: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<TypeKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_self_loop: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_fields: Option<Vec<String>>,
+}
+
+// Name of a type without the namespace of its semantic core, e.g. Account for lens.Account
+pub fn base_type_name(type_: &str) -> &str {
+    type_.rsplit('.').next().unwrap_or(type_)
 }
 
 pub enum SemanticCore {
     Social,
     Chat,
-    // Lens,
+    Lens,
     Project,
     Deep,
     ChatGPT,
 }
 
 impl SemanticCore {
-    pub fn get_types(&self) -> Vec<TypeDefinition> {
-        let json_str = match self {
+    pub const ALL: [SemanticCore; 6] = [
+        SemanticCore::Social,
+        SemanticCore::Chat,
+        SemanticCore::Lens,
+        SemanticCore::Project,
+        SemanticCore::Deep,
+        SemanticCore::ChatGPT,
+    ];
+
+    // Bundled JSON of the core, checked by the semcore binary and the tests below
+    pub fn json(&self) -> &'static str {
+        match self {
             SemanticCore::Social => include_str!("../semcores/social.json"),
             SemanticCore::Chat => include_str!("../semcores/chat.json"),
-            // SemanticCore::Lens => include_str!("../semcores/lens.json"),
+            SemanticCore::Lens => include_str!("../semcores/lens.json"),
             SemanticCore::Project => include_str!("../semcores/project.json"),
             SemanticCore::Deep => include_str!("../semcores/deep.json"),
             SemanticCore::ChatGPT => include_str!("../semcores/chatgpt.json"),
-        };
+        }
+    }
 
+    pub fn get_types(&self) -> Vec<TypeDefinition> {
         // Parse JSON string into RawTypeDefinition entries
-        let raw_definitions: Vec<TypeDefinition> = serde_json::from_str(json_str)
+        let raw_definitions: Vec<TypeDefinition> = serde_json::from_str(self.json())
             .expect("Failed to parse semantic core JSON");
 
-        // Filter only Type definitions that have an ID field
+        // Filter only Type definitions that have an ID field,
+        // the base types (Type, Any) are created on instantiate and not redefined by cores
         raw_definitions
             .into_iter()
             .filter(|def| def.id.is_some() && def.type_ == "Type")
+            .filter(|def| !matches!(def.id.as_deref(), Some("Type") | Some("Any")))
+            .collect()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SemanticCore::Social => "social",
+            SemanticCore::Chat => "chat",
+            SemanticCore::Lens => "lens",
+            SemanticCore::Project => "project",
+            SemanticCore::Deep => "deep",
+            SemanticCore::ChatGPT => "chatgpt",
+        }
+    }
+
+    // Type definitions with every name defined by the core prefixed by `namespace.`,
+    // references to types outside of the core (Any, Type) are left as is
+    pub fn get_namespaced_types(&self, namespace: &str) -> Vec<TypeDefinition> {
+        let types = self.get_types();
+        let defined: Vec<String> = types.iter().filter_map(|def| def.id.clone()).collect();
+        let qualify = |name: Option<String>| {
+            name.map(|n| if defined.contains(&n) { format!("{}.{}", namespace, n) } else { n })
+        };
+
+        types
+            .iter()
+            .cloned()
+            .map(|def| TypeDefinition {
+                id: qualify(def.id),
+                from: qualify(def.from),
+                to: qualify(def.to),
+                ..def
+            })
             .collect()
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "social" => Some(SemanticCore::Social),
             "chat" => Some(SemanticCore::Chat),
             "project" => Some(SemanticCore::Project),
-            // "lens" => Some(SemanticCore::Lens),
+            "lens" => Some(SemanticCore::Lens),
             "deep" => Some(SemanticCore::Deep),
             "chatgpt" => Some(SemanticCore::ChatGPT),
             _ => None,
@@ -55,6 +122,149 @@ impl SemanticCore {
     }
 }
 
+// Base types created on instantiate, cores may reference them without defining them
+const BASE_TYPES: [&str; 2] = ["Type", "Any"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Severity {
+    // The core can't be loaded or its fixtures can't be created
+    Error,
+    // The core loads on its own but conflicts with another bundled core
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+// Checks a semantic core file the way instantiate and the fixture cyberlinks would use it.
+// `name` is the core the file stands for, it is not compared against its bundled version
+pub fn lint_core(name: &str, json: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut error = |message: String| diagnostics.push(Diagnostic { severity: Severity::Error, message });
+
+    let entries: Vec<TypeDefinition> = match serde_json::from_str(json) {
+        Ok(entries) => entries,
+        Err(err) => {
+            error(format!("invalid semantic core JSON: {}", err));
+            return diagnostics;
+        }
+    };
+
+    // Type definitions, base types are created on instantiate and skipped by the loader
+    let mut types: BTreeMap<String, &TypeDefinition> = BTreeMap::new();
+    for (i, def) in entries.iter().enumerate().filter(|(_, def)| def.type_ == "Type") {
+        let Some(id) = def.id.as_deref() else {
+            error(format!("entry {}: type definition without an id", i));
+            continue;
+        };
+        if BASE_TYPES.contains(&id) {
+            continue;
+        }
+        if types.insert(id.to_string(), def).is_some() {
+            error(format!("entry {}: duplicate type id '{}'", i, id));
+        }
+    }
+    // Like in deep, any named entry can be used as a type, with permissive rules
+    let named: BTreeMap<&str, &TypeDefinition> = entries
+        .iter()
+        .filter_map(|def| def.id.as_deref().map(|id| (id, def)))
+        .collect();
+    let is_type = |name: &str| BASE_TYPES.contains(&name) || named.contains_key(name);
+
+    for (id, def) in &types {
+        for (side, constraint) in [("from", &def.from), ("to", &def.to)] {
+            if let Some(constraint) = constraint.as_deref().filter(|c| !is_type(c)) {
+                error(format!("type '{}': {} constraint '{}' is not a defined type", id, side, constraint));
+            }
+        }
+    }
+
+    // Fixtures get the FIDs instantiate would give them, named by their id or numbered per type
+    let mut fids: BTreeMap<String, String> = types.keys().map(|id| (id.clone(), "Type".to_string())).collect();
+    for base in BASE_TYPES {
+        fids.insert(base.to_string(), "Type".to_string());
+    }
+    let mut counters: BTreeMap<&str, u64> = BTreeMap::new();
+    for (i, fixture) in entries.iter().enumerate().filter(|(_, def)| def.type_ != "Type") {
+        let fid = match &fixture.id {
+            Some(id) => id.clone(),
+            None => {
+                let counter = counters.entry(&fixture.type_).or_insert(0);
+                *counter += 1;
+                format!("{}:{}", fixture.type_, counter)
+            }
+        };
+        let label = format!("entry {} ({})", i, fid);
+
+        // Named entries used as types aren't registered, so they get the permissive rules
+        let definition = match types.get(&fixture.type_) {
+            Some(def) => Some((*def, def.kind.clone().unwrap_or_else(|| TypeKind::infer(&def.from, &def.to)), def.allow_self_loop.unwrap_or(false))),
+            None => fids.contains_key(&fixture.type_)
+                .then(|| named.get(fixture.type_.as_str()))
+                .flatten()
+                .map(|def| (*def, TypeKind::Either, true)),
+        };
+        match definition {
+            None if !BASE_TYPES.contains(&fixture.type_.as_str()) => {
+                error(format!("{}: type '{}' is not defined", label, fixture.type_));
+            }
+            None => {}
+            Some((def, kind, allow_self_loop)) => {
+                match (&kind, fixture.from.is_some(), fixture.to.is_some()) {
+                    (TypeKind::Node, false, false) | (TypeKind::Edge, true, true) | (TypeKind::Either, _, _) => {}
+                    (TypeKind::Node, _, _) => error(format!("{}: '{}' is a node type, from and to must be absent", label, fixture.type_)),
+                    (TypeKind::Edge, _, _) => error(format!("{}: '{}' is an edge type, from and to must both be set", label, fixture.type_)),
+                }
+                if let (Some(from), Some(to)) = (&fixture.from, &fixture.to) {
+                    if from == to && !allow_self_loop {
+                        error(format!("{}: self-loop on '{}' is not allowed for type '{}'", label, from, fixture.type_));
+                    }
+                }
+                for (side, endpoint, constraint) in [("from", &fixture.from, &def.from), ("to", &fixture.to, &def.to)] {
+                    let Some(endpoint) = endpoint else { continue };
+                    match fids.get(endpoint) {
+                        None => error(format!("{}: {} '{}' doesn't name an earlier entry or type", label, side, endpoint)),
+                        Some(endpoint_type) => {
+                            if let Some(expected) = constraint.as_deref().filter(|c| *c != "Any" && c != endpoint_type) {
+                                error(format!("{}: {} '{}' is a '{}', expected '{}'", label, side, endpoint, endpoint_type, expected));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if fids.insert(fid.clone(), fixture.type_.clone()).is_some() {
+            error(format!("{}: duplicate id '{}'", label, fid));
+        }
+    }
+
+    // Non-namespaced cores share one set of names
+    for other in SemanticCore::ALL.iter().filter(|core| core.name() != name) {
+        let other_ids: BTreeSet<String> = other.get_types().into_iter().filter_map(|def| def.id).collect();
+        for id in types.keys().filter(|id| other_ids.contains(*id)) {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                message: format!("type '{}' is also defined by core '{}', load one of them namespaced", id, other.name()),
+            });
+        }
+    }
+
+    diagnostics
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -84,4 +294,67 @@ mod tests {
         assert!(has_chat, "Should have Chat type");
         assert!(has_message, "Should have Message type");
     }
+
+    #[test]
+    fn test_base_types_are_not_redefined() {
+        for core in [SemanticCore::Project, SemanticCore::Deep] {
+            let types = core.get_types();
+            assert!(!types.is_empty());
+            assert!(types.iter().all(|t| !matches!(t.id.as_deref(), Some("Type") | Some("Any"))));
+        }
+    }
+
+    #[test]
+    fn test_namespaced_lens_types() {
+        let types = SemanticCore::Lens.get_namespaced_types("lens");
+        assert!(!types.is_empty(), "Should load lens types");
+
+        let follows = types.iter().find(|t| t.id.as_deref() == Some("lens.Follows")).unwrap();
+        assert_eq!(follows.from.as_deref(), Some("lens.Account"));
+        assert_eq!(follows.to.as_deref(), Some("lens.Account"));
+
+        // References outside of the core stay global
+        let comments_on = types.iter().find(|t| t.id.as_deref() == Some("lens.CommentsOn")).unwrap();
+        assert_eq!(comments_on.to.as_deref(), Some("Any"));
+        assert!(types.iter().all(|t| t.id.as_ref().unwrap().starts_with("lens.")));
+    }
+
+    #[test]
+    fn test_lint_core() {
+        for core in SemanticCore::ALL {
+            let errors: Vec<_> = lint_core(core.name(), core.json())
+                .into_iter()
+                .filter(|d| d.severity == Severity::Error)
+                .collect();
+            assert!(errors.is_empty(), "{}: {:?}", core.name(), errors);
+        }
+
+        let json = r#"[
+            {"id": "Account", "type": "Type"},
+            {"id": "Account", "type": "Type"},
+            {"id": "Follows", "type": "Type", "from": "Account", "to": "Acount"},
+            {"type": "Account"},
+            {"type": "Follows", "from": "Account:1", "to": "Account:2"},
+            {"type": "Post"},
+            {"id": "Likes", "type": "Type", "from": "Account", "to": "Account"},
+            {"id": "Knows", "type": "Type", "from": "Account", "to": "Account", "allow_self_loop": true},
+            {"type": "Likes", "from": "Account:1", "to": "Account:1"},
+            {"type": "Knows", "from": "Account:1", "to": "Account:1"}
+        ]"#;
+        let messages: Vec<String> = lint_core("custom", json).iter().map(|d| d.to_string()).collect();
+        assert_eq!(messages, vec![
+            "error: entry 1: duplicate type id 'Account'",
+            "error: type 'Follows': to constraint 'Acount' is not a defined type",
+            "error: entry 4 (Follows:1): to 'Account:2' doesn't name an earlier entry or type",
+            "error: entry 5 (Post:1): type 'Post' is not defined",
+            "error: entry 8 (Likes:1): self-loop on 'Account:1' is not allowed for type 'Likes'",
+            "warning: type 'Account' is also defined by core 'social', load one of them namespaced",
+            "warning: type 'Follows' is also defined by core 'social', load one of them namespaced",
+            "warning: type 'Account' is also defined by core 'lens', load one of them namespaced",
+            "warning: type 'Follows' is also defined by core 'lens', load one of them namespaced",
+        ]);
+
+        let invalid = lint_core("custom", "[{\"type\": }]");
+        assert!(invalid[0].message.starts_with("invalid semantic core JSON"));
+    }
 }
@@ -1,6 +1,6 @@
 use cosmwasm_schema::cw_serde;
 
-use cosmwasm_std::{Addr, Timestamp};
+use cosmwasm_std::{Addr, Decimal, OverflowError, Timestamp, Uint128};
 use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 #[cw_serde]
@@ -14,6 +14,9 @@ pub struct CyberlinkState {
     pub owner: Addr,
     pub created_at: Timestamp,
     pub updated_at: Option<Timestamp>,
+    // Set while the cyberlink is deleted, only returned by queries that include deleted cyberlinks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<Timestamp>,
 }
 
 // Define the primary key namespace
@@ -37,6 +40,11 @@ pub struct CyberlinkIndices<'a> {
     // TODO WIP in design stage
     pub created_at: MultiIndex<'a, (Addr, u64), CyberlinkState, u64>,
     pub updated_at: MultiIndex<'a, (Addr, u64), CyberlinkState, u64>,
+    // Index by type and time across all owners (nanos), updated falls back to created
+    pub type_created_at: MultiIndex<'a, (String, u64), CyberlinkState, u64>,
+    pub type_updated_at: MultiIndex<'a, (String, u64), CyberlinkState, u64>,
+    // Index by from, type and creation time (nanos), the per-author timeline of a link type
+    pub from_type_created_at: MultiIndex<'a, (String, String, u64), CyberlinkState, u64>,
 }
 
 // Implement IndexList for CyberlinkIndices
@@ -45,7 +53,9 @@ impl<'a> IndexList<CyberlinkState> for CyberlinkIndices<'a> {
         let v: Vec<&dyn Index<CyberlinkState>> = vec![
             &self.owner, &self.type_, &self.from, &self.to, 
             &self.owner_type,
-            &self.created_at, &self.updated_at, &self.fid
+            &self.created_at, &self.updated_at, &self.fid,
+            &self.type_created_at, &self.type_updated_at,
+            &self.from_type_created_at
         ];
         Box::new(v.into_iter())
     }
@@ -89,6 +99,21 @@ pub fn cyberlinks<'a>() -> IndexedMap<u64, CyberlinkState, CyberlinkIndices<'a>>
             CYBERLINKS_KEY,
             "cyberlinks__updated_at",
         ),
+        type_created_at: MultiIndex::new(
+            |_pk, d: &CyberlinkState| (d.type_.clone(), d.created_at.nanos()),
+            CYBERLINKS_KEY,
+            "cyberlinks__type_created_at",
+        ),
+        type_updated_at: MultiIndex::new(
+            |_pk, d: &CyberlinkState| (d.type_.clone(), d.updated_at.map_or(d.created_at.nanos(), |t| t.nanos())),
+            CYBERLINKS_KEY,
+            "cyberlinks__type_updated_at",
+        ),
+        from_type_created_at: MultiIndex::new(
+            |_pk, d: &CyberlinkState| (d.from.clone(), d.type_.clone(), d.created_at.nanos()),
+            CYBERLINKS_KEY,
+            "cyberlinks__from_type_created_at",
+        ),
         fid: MultiIndex::new(
             |pk, d: &CyberlinkState| d.fid.clone().unwrap_or_else(|| format!("root:{}-{:?}", d.owner, pk)),
             CYBERLINKS_KEY,
@@ -102,6 +127,68 @@ pub fn cyberlinks<'a>() -> IndexedMap<u64, CyberlinkState, CyberlinkIndices<'a>>
 pub const NAMED_CYBERLINKS_KEY: &str = "named_cyberlinks";
 pub const NAMED_CYBERLINKS: Map<&str, u64> = Map::new(NAMED_CYBERLINKS_KEY);
 
+// Shape of the cyberlinks a type allows
+#[cw_serde]
+pub enum TypeKind {
+    // from and to must both be absent
+    Node,
+    // from and to must both be present
+    Edge,
+    // Either a node or an edge
+    Either,
+}
+
+impl TypeKind {
+    // Kind of a type definition which doesn't declare one explicitly
+    pub fn infer(from: &Option<String>, to: &Option<String>) -> Self {
+        if from.is_none() && to.is_none() {
+            TypeKind::Node
+        } else {
+            TypeKind::Edge
+        }
+    }
+}
+
+// Lifecycle state of a type
+#[cw_serde]
+pub enum TypeLifecycle {
+    Active,
+    // Accepts new cyberlinks but flags them with a warning
+    Deprecated,
+    // Rejects new cyberlinks, existing ones can still be queried and deleted
+    Retired,
+}
+
+// Type registry, keyed by type name
+#[cw_serde]
+pub struct TypeMeta {
+    // Semantic core the type was loaded from, if any
+    pub semcore: Option<String>,
+    pub kind: TypeKind,
+    // Whether an edge of this type may point from a node to itself
+    pub allow_self_loop: bool,
+    pub lifecycle: TypeLifecycle,
+    // Top-level value fields maintained in the field index for lookups by value
+    #[serde(default)]
+    pub indexed_fields: Vec<String>,
+}
+
+impl TypeMeta {
+    // Rules for types outside of the registry, e.g. deep-style named links used as types
+    pub fn unregistered() -> Self {
+        TypeMeta {
+            semcore: None,
+            kind: TypeKind::Either,
+            allow_self_loop: true,
+            lifecycle: TypeLifecycle::Active,
+            indexed_fields: vec![],
+        }
+    }
+}
+
+pub const TYPES_KEY: &str = "types";
+pub const TYPES: Map<&str, TypeMeta> = Map::new(TYPES_KEY);
+
 // ID counter
 pub const GID_KEY: &str = "gid";
 pub const GID: Item<u64> = Item::new(GID_KEY);
@@ -114,10 +201,113 @@ pub const TYPE_GIDS: Map<&str, u64> = Map::new(TYPE_GID_KEY);
 pub const DELETED_GIDS_KEY: &str = "deleted_gids";
 pub const DELETED_GIDS: Map<u64, bool> = Map::new(DELETED_GIDS_KEY);
 
+// Who deleted a cyberlink and when, kept until the cyberlink is restored or purged
+#[cw_serde]
+pub struct Tombstone {
+    pub deleted_at: Timestamp,
+    pub deleted_by: Addr,
+}
+
+pub const TOMBSTONES_KEY: &str = "tombstones";
+
+pub struct TombstoneIndices<'a> {
+    // Index by deletion time (nanos)
+    pub deleted_at: MultiIndex<'a, u64, Tombstone, u64>,
+}
+
+impl<'a> IndexList<Tombstone> for TombstoneIndices<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Tombstone>> + '_> {
+        let v: Vec<&dyn Index<Tombstone>> = vec![&self.deleted_at];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn tombstones<'a>() -> IndexedMap<u64, Tombstone, TombstoneIndices<'a>> {
+    let indices = TombstoneIndices {
+        deleted_at: MultiIndex::new(
+            |_pk, d: &Tombstone| d.deleted_at.nanos(),
+            TOMBSTONES_KEY,
+            "tombstones__deleted_at",
+        ),
+    };
+    IndexedMap::new(TOMBSTONES_KEY, indices)
+}
+
+// Global change log, sequenced across all owners for incremental sync
+#[cw_serde]
+pub enum ChangeOp {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Purge,
+    Transfer,
+}
+
+#[cw_serde]
+pub struct Change {
+    pub gid: u64,
+    pub op: ChangeOp,
+    pub time: Timestamp,
+}
+
+pub const CHANGE_SEQ_KEY: &str = "change_seq";
+pub const CHANGE_SEQ: Item<u64> = Item::new(CHANGE_SEQ_KEY);
+
+pub const CHANGES_KEY: &str = "changes";
+pub const CHANGES: Map<u64, Change> = Map::new(CHANGES_KEY);
+
+// Per-node access list managed by the owner of the cyberlink
+#[cw_serde]
+#[derive(Default)]
+pub struct NodeAcl {
+    pub editors: Vec<Addr>,
+    pub deleters: Vec<Addr>,
+}
+
+impl NodeAcl {
+    pub fn is_editor(&self, addr: impl AsRef<str>) -> bool {
+        let addr = addr.as_ref();
+        self.editors.iter().any(|a| a.as_ref() == addr)
+    }
+
+    pub fn is_deleter(&self, addr: impl AsRef<str>) -> bool {
+        let addr = addr.as_ref();
+        self.deleters.iter().any(|a| a.as_ref() == addr)
+    }
+}
+
+pub const NODE_ACL_KEY: &str = "node_acl";
+pub const NODE_ACL: Map<u64, NodeAcl> = Map::new(NODE_ACL_KEY);
+
+// Ownership transfers waiting for the recipient to accept, by GID
+pub const PENDING_TRANSFERS_KEY: &str = "pending_transfers";
+pub const PENDING_TRANSFERS: Map<u64, Addr> = Map::new(PENDING_TRANSFERS_KEY);
+
 #[cw_serde]
 pub struct Config {
     pub admins: Vec<Addr>,
-    pub executors: Vec<Addr>
+    pub executors: Vec<Addr>,
+    #[serde(default)]
+    pub handle_fee: Option<HandleFee>,
+}
+
+// Fee for claiming a handle, shorter handles cost more
+#[cw_serde]
+pub struct HandleFee {
+    pub denom: String,
+    // Fee for handles of HANDLE_FEE_BASE_LENGTH characters or longer,
+    // doubled for every character below that length
+    pub amount: Uint128,
+}
+
+pub const HANDLE_FEE_BASE_LENGTH: usize = 8;
+
+impl HandleFee {
+    pub fn for_handle(&self, handle: &str) -> Result<Uint128, OverflowError> {
+        let shortfall = HANDLE_FEE_BASE_LENGTH.saturating_sub(handle.chars().count()) as u32;
+        self.amount.checked_mul(Uint128::new(1u128 << shortfall))
+    }
 }
 
 impl Config {
@@ -143,6 +333,39 @@ impl Config {
 pub const CONFIG_KEY: &str = "config";
 pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
 
+// Handle registry, keyed by lowercase handle
+#[cw_serde]
+pub struct Handle {
+    pub owner: Addr,
+    // FID of the Account the handle resolves to
+    pub account: String,
+}
+
+pub const HANDLES_KEY: &str = "handles";
+
+pub struct HandleIndices<'a> {
+    // Index by the FID of the Account
+    pub account: MultiIndex<'a, String, Handle, String>,
+}
+
+impl<'a> IndexList<Handle> for HandleIndices<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Handle>> + '_> {
+        let v: Vec<&dyn Index<Handle>> = vec![&self.account];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn handles<'a>() -> IndexedMap<&'a str, Handle, HandleIndices<'a>> {
+    let indices = HandleIndices {
+        account: MultiIndex::new(
+            |_pk, d: &Handle| d.account.clone(),
+            HANDLES_KEY,
+            "handles__account",
+        ),
+    };
+    IndexedMap::new(HANDLES_KEY, indices)
+}
+
 // Stateful Counts (Tier 4)
 pub const OWNER_LINK_COUNT_KEY: &str = "owner_link_count";
 pub const OWNER_LINK_COUNT: Map<&Addr, u64> = Map::new(OWNER_LINK_COUNT_KEY);
@@ -154,3 +377,126 @@ pub const OWNER_TYPE_LINK_COUNT_KEY: &str = "owner_type_link_count";
 // Key is (Owner Addr, Type String)
 pub const OWNER_TYPE_LINK_COUNT: Map<(&Addr, &str), u64> = Map::new(OWNER_TYPE_LINK_COUNT_KEY);
 
+
+// Degree counters per node FID, over live cyberlinks.
+// "Any" stands for a missing endpoint and isn't counted
+pub const IN_DEGREE_KEY: &str = "in_degree";
+pub const IN_DEGREE: Map<&str, u64> = Map::new(IN_DEGREE_KEY);
+
+pub const OUT_DEGREE_KEY: &str = "out_degree";
+pub const OUT_DEGREE: Map<&str, u64> = Map::new(OUT_DEGREE_KEY);
+
+// Key is (Node FID, Link Type)
+pub const IN_DEGREE_BY_TYPE_KEY: &str = "in_degree_by_type";
+pub const IN_DEGREE_BY_TYPE: Map<(&str, &str), u64> = Map::new(IN_DEGREE_BY_TYPE_KEY);
+
+pub const OUT_DEGREE_BY_TYPE_KEY: &str = "out_degree_by_type";
+pub const OUT_DEGREE_BY_TYPE: Map<(&str, &str), u64> = Map::new(OUT_DEGREE_BY_TYPE_KEY);
+
+// Reaction tallies over live ReactsTo links, key is (Target FID, Reaction Value)
+pub const REACTION_TALLIES_KEY: &str = "reaction_tallies";
+pub const REACTION_TALLIES: Map<(&str, &str), u64> = Map::new(REACTION_TALLIES_KEY);
+
+// GID of the reaction of each owner to a target, key is (Target FID, Owner Addr)
+pub const OWNER_REACTIONS_KEY: &str = "owner_reactions";
+pub const OWNER_REACTIONS: Map<(&str, &Addr), u64> = Map::new(OWNER_REACTIONS_KEY);
+
+// Live cyberlinks by the value of an indexed field, key is ((Type, Field), Value, GID)
+pub const FIELD_INDEX_KEY: &str = "field_index";
+pub const FIELD_INDEX: Map<((&str, &str), &str, u64), bool> = Map::new(FIELD_INDEX_KEY);
+
+// FIDs created by subgraph imports, key is (Importer Addr, Import name, Source FID)
+pub const IMPORTED_FIDS_KEY: &str = "imported_fids";
+pub const IMPORTED_FIDS: Map<(&Addr, &str, &str), String> = Map::new(IMPORTED_FIDS_KEY);
+
+// Ranking runs: PageRank over selected link types, computed in bounded chunks across transactions
+#[cw_serde]
+pub enum RankPhase {
+    // Removing the working data of the previous run
+    Clear,
+    // Collecting the nodes and out-degrees of the selected links
+    Collect,
+    // Spreading the scores of the current iteration along the links
+    Distribute,
+    // Computing the scores of the next iteration
+    Apply,
+    // Writing the final scores
+    Publish,
+    // Removing scores of nodes that dropped out of the graph
+    Prune,
+    Done,
+}
+
+#[cw_serde]
+pub struct RankRun {
+    pub link_types: Vec<String>,
+    pub damping: Decimal,
+    pub iterations: u32,
+    pub iteration: u32,
+    pub phase: RankPhase,
+    // Links created after the run started are left out
+    pub max_gid: u64,
+    // Position within the phase, links are read per type by GID and nodes by FID
+    pub type_index: u32,
+    pub link_cursor: Option<u64>,
+    pub node_cursor: Option<String>,
+    pub node_count: u64,
+    // Nodes with outgoing links
+    pub linked_count: u64,
+    // Score mass of nodes without outgoing links, spread evenly over all nodes
+    pub dangling: Decimal,
+    pub next_dangling: Decimal,
+}
+
+pub const RANK_RUN_KEY: &str = "rank_run";
+pub const RANK_RUN: Item<RankRun> = Item::new(RANK_RUN_KEY);
+
+#[cw_serde]
+pub struct RankNode {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub out_degree: u64,
+}
+
+// Working data of the ranking run, by node FID
+pub const RANK_NODES_KEY: &str = "rank_nodes";
+pub const RANK_NODES: Map<&str, RankNode> = Map::new(RANK_NODES_KEY);
+
+pub const RANK_CURRENT_KEY: &str = "rank_current";
+pub const RANK_CURRENT: Map<&str, Decimal> = Map::new(RANK_CURRENT_KEY);
+
+pub const RANK_NEXT_KEY: &str = "rank_next";
+pub const RANK_NEXT: Map<&str, Decimal> = Map::new(RANK_NEXT_KEY);
+
+// Published scores of the last finished run, by node FID
+#[cw_serde]
+pub struct RankScore {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub score: Decimal,
+}
+
+pub const RANK_SCORES_KEY: &str = "rank_scores";
+
+pub struct RankScoreIndices<'a> {
+    // Index by node type and score
+    pub type_score: MultiIndex<'a, (String, u128), RankScore, String>,
+}
+
+impl<'a> IndexList<RankScore> for RankScoreIndices<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<RankScore>> + '_> {
+        let v: Vec<&dyn Index<RankScore>> = vec![&self.type_score];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn rank_scores<'a>() -> IndexedMap<&'a str, RankScore, RankScoreIndices<'a>> {
+    let indices = RankScoreIndices {
+        type_score: MultiIndex::new(
+            |_pk, d: &RankScore| (d.type_.clone(), d.score.atomics().u128()),
+            RANK_SCORES_KEY,
+            "rank_scores__type_score",
+        ),
+    };
+    IndexedMap::new(RANK_SCORES_KEY, indices)
+}